@@ -6,10 +6,12 @@ use std::path::Path;
 use std::{env, fs};
 
 fn choose_source_dir() -> Option<String> {
-    // Follow the 'recommended' install path
-    if let Ok(path) = env::var("NDI_RUNTIME_DIR_V3") {
-        if Path::new(&path).exists() {
-            return Some(path);
+    // Follow the 'recommended' install path, preferring the newest runtime
+    for var in &["NDI_RUNTIME_DIR_V5", "NDI_RUNTIME_DIR_V4", "NDI_RUNTIME_DIR_V3"] {
+        if let Ok(path) = env::var(var) {
+            if Path::new(&path).exists() {
+                return Some(path);
+            }
         }
     }
 
@@ -57,13 +59,21 @@ fn main() {
     if let Some(path) = source_dir {
         let source_path = Path::new(&path);
         let dest_path = Path::new(&env::var("OUT_DIR").unwrap()).join("../../../deps");
-        fs::copy(source_path.join("libndi.so.3"), dest_path.join("libndi.so.3")).expect("copy libndi.so.3");
 
-        let sl_res = std::os::unix::fs::symlink(Path::new("libndi.so.3"), dest_path.join("libndi.so"));
-        if let Err(e) = sl_res {
-            if e.kind() != ErrorKind::AlreadyExists {
-                panic!("Unknown error: {}", e);
+        // Use the newest versioned soname that the runtime provides
+        let soname = ["libndi.so.5", "libndi.so.4", "libndi.so.3"]
+            .iter()
+            .find(|name| source_path.join(name).exists());
+        if let Some(soname) = soname {
+            fs::copy(source_path.join(soname), dest_path.join(soname)).expect("copy libndi.so");
+
+            let link_path = dest_path.join("libndi.so");
+            if let Err(e) = fs::remove_file(&link_path) {
+                if e.kind() != ErrorKind::NotFound {
+                    panic!("Unknown error: {}", e);
+                }
             }
+            std::os::unix::fs::symlink(Path::new(soname), link_path).expect("symlink libndi.so");
         }
     }
 
@@ -48,16 +48,13 @@ fn main() {
                 ReceiveCaptureResult::None => println!("No data received."),
                 ReceiveCaptureResult::Video(video) => {
                     println!("Video data received ({}x{}).", video.width, video.height);
-                    if let Some(data) = video.lock_data() {
-                        println!("  Got {} bytes", data.len());
-                    }
+                    println!("  Got {} bytes", video.lock_data().len());
                 }
                 ReceiveCaptureResult::Audio(audio) => {
                     println!("Audio data received ({} samples).", audio.sample_count);
-                    if let Some(data) = audio.lock_data() {
-                        println!("  Got {} bytes", data.len());
-                    }
+                    println!("  Got {} bytes", audio.lock_data().len());
                 }
+                ReceiveCaptureResult::StatusChange => println!("Receiver status changed."),
                 _ => {}
             },
         }
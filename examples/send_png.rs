@@ -1,7 +1,8 @@
 extern crate ndi_sdk;
 extern crate png;
 
-use ndi_sdk::send::SendColorFormat;
+use ndi_sdk::send::FourCCType;
+use ndi_sdk::time::FrameRate;
 use ndi_sdk::NDIInstance;
 use std::fs::File;
 
@@ -14,7 +15,7 @@ fn main() {
     reader.next_frame(&mut buf).unwrap();
 
     // Create an NDI source that is called "My PNG" and is clocked to the video.
-    let mut sender = instance
+    let sender = instance
         .create_send_instance("My PNG".to_string(), false, false)
         .expect("Expected sender instance to be created");
 
@@ -24,7 +25,8 @@ fn main() {
         info.height as i32,
         ndi_sdk::send::FrameFormatType::Progressive,
     )
-    .with_data(buf, info.width as i32 * 4, SendColorFormat::Rgba)
+    .with_data(buf, info.width as i32 * 4, FourCCType::RGBA)
+    .with_framerate(FrameRate::FPS_29_97)
     .build()
     .expect("Expected frame to be created");
 
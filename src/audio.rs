@@ -0,0 +1,144 @@
+//! Helpers for working with the planar floating point audio used by NDI
+
+pub mod matrix;
+pub mod meter;
+pub mod resample;
+
+use crate::receive::AudioFrame;
+use crate::send::{create_ndi_send_audio_frame, NDISendAudioFrameBuilder};
+use crate::time::{Timecode, Timestamp};
+
+/// Planar audio with one buffer per channel, as produced by the resampler and channel matrix
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioBuffer {
+    pub sample_rate: i32,
+    /// The timecode of the first sample
+    pub timecode: Timecode,
+    /// The timestamp of the first sample
+    pub timestamp: Timestamp,
+    /// The samples of each channel, which must all be the same length
+    pub channels: Vec<Vec<f32>>,
+}
+impl AudioBuffer {
+    /// Copy the samples of a received frame
+    pub fn from_frame(frame: &AudioFrame) -> AudioBuffer {
+        let data = frame.lock_data();
+        AudioBuffer {
+            sample_rate: frame.sample_rate,
+            timecode: frame.timecode,
+            timestamp: frame.timestamp,
+            channels: data.channels().map(|c| c.to_vec()).collect(),
+        }
+    }
+
+    /// The number of samples in each channel
+    pub fn sample_count(&self) -> usize {
+        self.channels.first().map_or(0, |c| c.len())
+    }
+
+    /// A builder for a frame to send these samples, with the same timecode and timestamp
+    pub fn into_send_frame_builder(self) -> NDISendAudioFrameBuilder {
+        create_ndi_send_audio_frame(self.channels.len() as i32, self.sample_rate)
+            .with_timecode(self.timecode)
+            .with_timestamp(self.timestamp)
+            .with_channels(self.channels)
+    }
+}
+
+/// The time taken by `samples` at `sample_rate`, in 100 ns units
+fn samples_to_ticks(samples: f64, sample_rate: i32) -> i64 {
+    (samples * 10_000_000.0 / sample_rate as f64).round() as i64
+}
+
+/// The integer sample value corresponding to a floating point sample of 1.0.
+///
+/// `reference_level` is the number of dB above the +4 dBU reference level of the floating point samples at which the
+/// integer samples reach full scale, as used by the SDK's interleaved audio functions
+fn integer_scale(full_scale: f64, reference_level: i32) -> f64 {
+    full_scale / 10f64.powf(reference_level as f64 / 20.0)
+}
+
+/// Interleave planar audio, converting each sample with `convert`
+fn interleave<T, F>(
+    planar: &[f32],
+    channel_count: usize,
+    sample_count: usize,
+    channel_stride: usize,
+    convert: F,
+) -> Vec<T>
+where
+    F: Fn(f32) -> T,
+{
+    let mut res = Vec::with_capacity(channel_count * sample_count);
+    for sample in 0..sample_count {
+        for channel in 0..channel_count {
+            res.push(convert(planar[channel * channel_stride + sample]));
+        }
+    }
+    res
+}
+
+/// Split interleaved audio into planes with a stride of `sample_count`, converting each sample with `convert`
+fn deinterleave<T, F>(interleaved: &[T], channel_count: usize, convert: F) -> Vec<f32>
+where
+    T: Copy,
+    F: Fn(T) -> f32,
+{
+    let sample_count = interleaved.len() / channel_count;
+    let mut res = vec![0.0; channel_count * sample_count];
+    for (sample, frame) in interleaved.chunks_exact(channel_count).enumerate() {
+        for (channel, s) in frame.iter().enumerate() {
+            res[channel * sample_count + sample] = convert(*s);
+        }
+    }
+    res
+}
+
+pub(crate) fn to_interleaved_i16(
+    planar: &[f32],
+    channel_count: usize,
+    sample_count: usize,
+    channel_stride: usize,
+    reference_level: i32,
+) -> Vec<i16> {
+    let scale = integer_scale(i16::MAX as f64, reference_level);
+    interleave(planar, channel_count, sample_count, channel_stride, |s| {
+        (s as f64 * scale).round().clamp(i16::MIN as f64, i16::MAX as f64) as i16
+    })
+}
+
+pub(crate) fn to_interleaved_i32(
+    planar: &[f32],
+    channel_count: usize,
+    sample_count: usize,
+    channel_stride: usize,
+    reference_level: i32,
+) -> Vec<i32> {
+    let scale = integer_scale(i32::MAX as f64, reference_level);
+    interleave(planar, channel_count, sample_count, channel_stride, |s| {
+        (s as f64 * scale).round().clamp(i32::MIN as f64, i32::MAX as f64) as i32
+    })
+}
+
+pub(crate) fn to_interleaved_f32(
+    planar: &[f32],
+    channel_count: usize,
+    sample_count: usize,
+    channel_stride: usize,
+) -> Vec<f32> {
+    interleave(planar, channel_count, sample_count, channel_stride, |s| s)
+}
+
+pub(crate) fn from_interleaved_i16(interleaved: &[i16], channel_count: usize, reference_level: i32) -> Vec<f32> {
+    let scale = integer_scale(i16::MAX as f64, reference_level);
+    deinterleave(interleaved, channel_count, |s| (s as f64 / scale) as f32)
+}
+
+pub(crate) fn from_interleaved_i32(interleaved: &[i32], channel_count: usize, reference_level: i32) -> Vec<f32> {
+    let scale = integer_scale(i32::MAX as f64, reference_level);
+    deinterleave(interleaved, channel_count, |s| (s as f64 / scale) as f32)
+}
+
+pub(crate) fn from_interleaved_f32(interleaved: &[f32], channel_count: usize) -> Vec<f32> {
+    deinterleave(interleaved, channel_count, |s| s)
+}
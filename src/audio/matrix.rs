@@ -0,0 +1,158 @@
+//! Mixing audio from one set of channels to another
+
+use super::AudioBuffer;
+use crate::receive::AudioFrame;
+use std::f32::consts::FRAC_1_SQRT_2;
+
+/// The gain applied by each input channel to each output channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelMatrix {
+    input_count: usize,
+    /// One row of gains for each output channel, with one gain for each input channel
+    gains: Vec<Vec<f32>>,
+}
+impl ChannelMatrix {
+    /// Create a matrix with every gain set to zero
+    pub fn silent(input_count: usize, output_count: usize) -> ChannelMatrix {
+        ChannelMatrix {
+            input_count,
+            gains: vec![vec![0.0; input_count]; output_count],
+        }
+    }
+
+    /// Create a matrix which passes every channel through unchanged
+    pub fn identity(channel_count: usize) -> ChannelMatrix {
+        let mut res = ChannelMatrix::silent(channel_count, channel_count);
+        for i in 0..channel_count {
+            res.gains[i][i] = 1.0;
+        }
+        res
+    }
+
+    /// Create a matrix where each output channel is a copy of the input channel in `routes`, or silent for None.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a route refers to an input which is not less than `input_count`
+    pub fn route(input_count: usize, routes: &[Option<usize>]) -> ChannelMatrix {
+        let mut res = ChannelMatrix::silent(input_count, routes.len());
+        for (output, input) in routes.iter().enumerate() {
+            if let Some(input) = *input {
+                res.set_gain(output, input, 1.0);
+            }
+        }
+        res
+    }
+
+    /// Create a matrix to convert between common channel layouts. Channels are assumed to be in the usual order of
+    /// L, R, C, LFE, Ls, Rs, then Lb, Rb for 7.1.
+    ///
+    /// * Mono is copied to both L and R
+    /// * 5.1 and 7.1 are mixed down to stereo as described by ITU-R BS.775, leaving out the LFE channel
+    /// * Anything mixed down to mono is first mixed down to stereo, then L and R are averaged
+    /// * Otherwise, channels are copied to the output channel with the same index, and extra output channels are silent
+    pub fn remix(input_count: usize, output_count: usize) -> ChannelMatrix {
+        match (input_count, output_count) {
+            (i, o) if i == o => ChannelMatrix::identity(i),
+            (1, o) => {
+                let routes: Vec<_> = (0..o).map(|c| if c < 2 { Some(0) } else { None }).collect();
+                ChannelMatrix::route(1, &routes)
+            }
+            (6, 2) | (8, 2) => {
+                let mut res = ChannelMatrix::silent(input_count, 2);
+                for (output, side) in [(0, 4), (1, 5)].iter() {
+                    res.set_gain(*output, *output, 1.0);
+                    res.set_gain(*output, 2, FRAC_1_SQRT_2);
+                    res.set_gain(*output, *side, FRAC_1_SQRT_2);
+                    if input_count == 8 {
+                        res.set_gain(*output, side + 2, FRAC_1_SQRT_2);
+                    }
+                }
+                res
+            }
+            (i, 1) => {
+                let stereo = ChannelMatrix::remix(i, 2);
+                let gains = (0..i)
+                    .map(|c| (stereo.gains[0][c] + stereo.gains[1][c]) / 2.0)
+                    .collect();
+                ChannelMatrix {
+                    input_count: i,
+                    gains: vec![gains],
+                }
+            }
+            (i, o) => {
+                let routes: Vec<_> = (0..o).map(|c| if c < i { Some(c) } else { None }).collect();
+                ChannelMatrix::route(i, &routes)
+            }
+        }
+    }
+
+    pub fn input_count(&self) -> usize {
+        self.input_count
+    }
+    pub fn output_count(&self) -> usize {
+        self.gains.len()
+    }
+
+    pub fn gain(&self, output: usize, input: usize) -> f32 {
+        self.gains[output][input]
+    }
+    /// Set the gain applied to `input` when mixing it into `output`
+    ///
+    /// # Panics
+    ///
+    /// Panics if either channel is out of range
+    pub fn set_gain(&mut self, output: usize, input: usize, gain: f32) {
+        assert!(
+            input < self.input_count,
+            "input {} out of range for matrix with {} inputs",
+            input,
+            self.input_count
+        );
+        self.gains[output][input] = gain;
+    }
+
+    /// Mix a received frame. Returns None if it does not have `input_count()` channels
+    pub fn process(&self, frame: &AudioFrame) -> Option<AudioBuffer> {
+        let data = frame.lock_data();
+        if data.channel_count() != self.input_count {
+            return None;
+        }
+        Some(AudioBuffer {
+            sample_rate: frame.sample_rate,
+            timecode: frame.timecode,
+            timestamp: frame.timestamp,
+            channels: self.mix(&data.channels().collect::<Vec<_>>()),
+        })
+    }
+
+    /// Mix a buffer. Returns None if it does not have `input_count()` channels
+    pub fn apply(&self, input: &AudioBuffer) -> Option<AudioBuffer> {
+        if input.channels.len() != self.input_count {
+            return None;
+        }
+        let channels: Vec<&[f32]> = input.channels.iter().map(|c| &c[..]).collect();
+        Some(AudioBuffer {
+            channels: self.mix(&channels),
+            ..*input
+        })
+    }
+
+    fn mix(&self, inputs: &[&[f32]]) -> Vec<Vec<f32>> {
+        let sample_count = inputs.iter().map(|c| c.len()).min().unwrap_or(0);
+        self.gains
+            .iter()
+            .map(|gains| {
+                let mut output = vec![0.0; sample_count];
+                for (input, gain) in inputs.iter().zip(gains) {
+                    if *gain != 0.0 {
+                        for (o, i) in output.iter_mut().zip(input.iter()) {
+                            *o += gain * i;
+                        }
+                    }
+                }
+                output
+            })
+            .collect()
+    }
+}
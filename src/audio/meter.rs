@@ -0,0 +1,402 @@
+//! Level and loudness metering for received audio.
+//!
+//! An `AudioMeter` is fed frames on the thread receiving audio, and publishes a `MeterSnapshot` after each one. Other
+//! threads, such as a UI, read the latest snapshot through a `MeterReader`.
+//!
+//! Loudness is measured as described by ITU-R BS.1770 and EBU R 128: the audio is K-weighted, then its mean square is
+//! taken over a 400 ms window for momentary loudness and a 3 s window for short-term loudness, both updated every 100 ms.
+//! Both windows start out silent.
+
+use crate::receive::AudioFrame;
+use std::f64::consts::PI;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// The number of 100 ms blocks in the momentary window
+const MOMENTARY_BLOCKS: usize = 4;
+/// The number of 100 ms blocks in the short-term window
+const SHORT_TERM_BLOCKS: usize = 30;
+
+/// The interpolation filter used for true peak measurement has this many taps for each oversampled phase
+const TRUE_PEAK_TAPS: usize = 12;
+
+/// Convert a linear sample level to dBFS
+pub fn to_dbfs(level: f32) -> f32 {
+    20.0 * level.log10()
+}
+
+/// Levels of a single channel. Peaks are linear, and held until reset, loudness is in LUFS
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChannelLevels {
+    /// The largest absolute sample value
+    pub peak: f32,
+    /// The largest absolute value of the signal between samples, estimated by oversampling
+    pub true_peak: f32,
+    /// The root mean square of the samples in the momentary window
+    pub rms: f32,
+    /// The loudness of this channel alone over the momentary window
+    pub momentary_lufs: f64,
+    /// The loudness of this channel alone over the short-term window
+    pub short_term_lufs: f64,
+}
+
+/// The levels of every channel, and the loudness of the whole programme
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeterSnapshot {
+    pub sample_rate: i32,
+    pub channels: Vec<ChannelLevels>,
+    /// The loudness of all channels together over the momentary window, in LUFS
+    pub momentary_lufs: f64,
+    /// The loudness of all channels together over the short-term window, in LUFS
+    pub short_term_lufs: f64,
+}
+
+struct Shared {
+    snapshot: Mutex<MeterSnapshot>,
+    reset_peaks: AtomicBool,
+}
+
+/// Reads the snapshots published by an `AudioMeter`, from any thread
+#[derive(Clone)]
+pub struct MeterReader {
+    shared: Arc<Shared>,
+}
+impl MeterReader {
+    /// The levels as of the last frame processed
+    pub fn snapshot(&self) -> MeterSnapshot {
+        self.shared
+            .snapshot
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+    /// Clear the held peaks before the next frame is processed
+    pub fn reset_peaks(&self) {
+        self.shared.reset_peaks.store(true, Ordering::Relaxed);
+    }
+}
+
+/// A second order IIR filter
+#[derive(Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// The two stages of the K-weighting filter from BS.1770, for any sample rate
+fn k_weighting(sample_rate: f64) -> (Biquad, Biquad) {
+    // A high shelf modelling the acoustic effect of the head
+    let (f0, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * f0 / sample_rate).tan();
+    let vh = 10f64.powf(gain / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    // A high pass filter, the RLB weighting curve
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * f0 / sample_rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let highpass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    (shelf, highpass)
+}
+
+/// A windowed sinc filter interpolating between the middle two samples of the history, with one set of taps for each
+/// oversampled position between them. Each set is normalised to unity gain at DC
+fn true_peak_filter(oversampling: usize) -> Vec<[f32; TRUE_PEAK_TAPS]> {
+    let half = (TRUE_PEAK_TAPS / 2) as f64;
+    (1..oversampling)
+        .map(|phase| {
+            // The distance of each sample in the history from the interpolated position
+            let offsets = (0..TRUE_PEAK_TAPS).map(|j| half - 1.0 - j as f64 + phase as f64 / oversampling as f64);
+            let taps: Vec<f64> = offsets
+                .map(|x| {
+                    let sinc = (PI * x).sin() / (PI * x);
+                    let window = 0.5 + 0.5 * (PI * x / half).cos();
+                    sinc * window
+                })
+                .collect();
+            let sum: f64 = taps.iter().sum();
+
+            let mut res = [0.0; TRUE_PEAK_TAPS];
+            for (r, t) in res.iter_mut().zip(taps) {
+                *r = (t / sum) as f32;
+            }
+            res
+        })
+        .collect()
+}
+
+/// How much to oversample for true peak measurement. Higher sample rates need less
+fn true_peak_oversampling(sample_rate: i32) -> usize {
+    if sample_rate < 96000 {
+        4
+    } else if sample_rate < 192000 {
+        2
+    } else {
+        1
+    }
+}
+
+struct ChannelState {
+    shelf: Biquad,
+    highpass: Biquad,
+    /// The most recent samples, oldest first
+    history: [f32; TRUE_PEAK_TAPS],
+
+    /// Sums of the squared samples, with and without K-weighting, in the current block
+    block_weighted: f64,
+    block_square: f64,
+    /// The mean squares of recent blocks, with and without K-weighting
+    weighted_blocks: [f64; SHORT_TERM_BLOCKS],
+    square_blocks: [f64; SHORT_TERM_BLOCKS],
+
+    peak: f32,
+    true_peak: f32,
+}
+impl ChannelState {
+    fn new(sample_rate: i32) -> ChannelState {
+        let (shelf, highpass) = k_weighting(sample_rate as f64);
+        ChannelState {
+            shelf,
+            highpass,
+            history: [0.0; TRUE_PEAK_TAPS],
+            block_weighted: 0.0,
+            block_square: 0.0,
+            weighted_blocks: [0.0; SHORT_TERM_BLOCKS],
+            square_blocks: [0.0; SHORT_TERM_BLOCKS],
+            peak: 0.0,
+            true_peak: 0.0,
+        }
+    }
+
+    fn process(&mut self, sample: f32, filter: &[[f32; TRUE_PEAK_TAPS]]) {
+        let abs = sample.abs();
+        if abs > self.peak {
+            self.peak = abs;
+        }
+
+        self.history.copy_within(1.., 0);
+        self.history[TRUE_PEAK_TAPS - 1] = sample;
+        let mut true_peak = abs;
+        for phase in filter {
+            let v: f32 = phase.iter().zip(self.history.iter()).map(|(t, s)| t * s).sum();
+            true_peak = true_peak.max(v.abs());
+        }
+        if true_peak > self.true_peak {
+            self.true_peak = true_peak;
+        }
+
+        let x = sample as f64;
+        let weighted = self.highpass.process(self.shelf.process(x));
+        self.block_weighted += weighted * weighted;
+        self.block_square += x * x;
+    }
+
+    fn end_block(&mut self, index: usize, block_len: usize) {
+        self.weighted_blocks[index] = self.block_weighted / block_len as f64;
+        self.square_blocks[index] = self.block_square / block_len as f64;
+        self.block_weighted = 0.0;
+        self.block_square = 0.0;
+    }
+}
+
+/// The mean of the last `count` blocks of a ring, where `next` is the index of the next block to be written
+fn window_mean(blocks: &[f64; SHORT_TERM_BLOCKS], next: usize, count: usize) -> f64 {
+    let sum: f64 = (1..=count)
+        .map(|i| blocks[(next + SHORT_TERM_BLOCKS - i) % SHORT_TERM_BLOCKS])
+        .sum();
+    sum / count as f64
+}
+
+/// Loudness in LUFS of a K-weighted mean square. Silence is negative infinity
+fn to_lufs(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.log10()
+}
+
+/// Measures the levels and loudness of a stream of audio
+pub struct AudioMeter {
+    sample_rate: i32,
+    channels: Vec<ChannelState>,
+    weights: Vec<f64>,
+    true_peak_filter: Vec<[f32; TRUE_PEAK_TAPS]>,
+
+    /// The number of samples in each 100 ms block
+    block_len: usize,
+    /// The number of samples processed in the current block
+    block_pos: usize,
+    /// The index of the next block to be written in each channel's ring
+    next_block: usize,
+
+    shared: Arc<Shared>,
+}
+impl Default for AudioMeter {
+    fn default() -> Self {
+        AudioMeter::new()
+    }
+}
+impl AudioMeter {
+    pub fn new() -> AudioMeter {
+        AudioMeter {
+            sample_rate: 0,
+            channels: Vec::new(),
+            weights: Vec::new(),
+            true_peak_filter: Vec::new(),
+            block_len: 0,
+            block_pos: 0,
+            next_block: 0,
+            shared: Arc::new(Shared {
+                snapshot: Mutex::new(MeterSnapshot::default()),
+                reset_peaks: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// A reader for the snapshots published by this meter
+    pub fn reader(&self) -> MeterReader {
+        MeterReader {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Set how much a channel contributes to the programme loudness. Every channel has a weight of 1.0 by default.
+    /// BS.1770 uses 1.41 for surround channels, and 0.0 to exclude the LFE channel
+    pub fn set_channel_weight(&mut self, channel: usize, weight: f64) {
+        if self.weights.len() <= channel {
+            self.weights.resize(channel + 1, 1.0);
+        }
+        self.weights[channel] = weight;
+    }
+
+    /// Clear all measurements, including the loudness windows
+    pub fn reset(&mut self) {
+        self.sample_rate = 0;
+        self.channels.clear();
+        self.publish();
+    }
+
+    /// Clear the held peaks
+    pub fn reset_peaks(&mut self) {
+        for channel in &mut self.channels {
+            channel.peak = 0.0;
+            channel.true_peak = 0.0;
+        }
+        self.publish();
+    }
+
+    /// Measure a received frame
+    pub fn process(&mut self, frame: &AudioFrame) {
+        self.process_planar(frame.lock_data().channels(), frame.sample_rate);
+    }
+
+    /// Measure audio with one slice of samples per channel, which must all be the same length.
+    /// The measurements are reset if the sample rate or number of channels changes
+    pub fn process_planar<'a, I>(&mut self, channels: I, sample_rate: i32)
+    where
+        I: IntoIterator<Item = &'a [f32]>,
+    {
+        let channels: Vec<&[f32]> = channels.into_iter().collect();
+        if sample_rate <= 0 {
+            return;
+        }
+        if sample_rate != self.sample_rate || channels.len() != self.channels.len() {
+            self.configure(sample_rate, channels.len());
+        }
+        if self.shared.reset_peaks.swap(false, Ordering::Relaxed) {
+            self.reset_peaks();
+        }
+
+        let sample_count = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+        let mut pos = 0;
+        while pos < sample_count {
+            // Process up to the end of the current block
+            let len = (self.block_len - self.block_pos).min(sample_count - pos);
+            for (state, samples) in self.channels.iter_mut().zip(channels.iter()) {
+                for sample in &samples[pos..pos + len] {
+                    state.process(*sample, &self.true_peak_filter);
+                }
+            }
+            pos += len;
+            self.block_pos += len;
+
+            if self.block_pos == self.block_len {
+                for state in &mut self.channels {
+                    state.end_block(self.next_block, self.block_len);
+                }
+                self.next_block = (self.next_block + 1) % SHORT_TERM_BLOCKS;
+                self.block_pos = 0;
+            }
+        }
+
+        self.publish();
+    }
+
+    /// The levels as of the last frame processed
+    pub fn snapshot(&self) -> MeterSnapshot {
+        let mut momentary = 0.0;
+        let mut short_term = 0.0;
+        let channels = self
+            .channels
+            .iter()
+            .enumerate()
+            .map(|(i, state)| {
+                let weight = self.weights.get(i).cloned().unwrap_or(1.0);
+                let channel_momentary = window_mean(&state.weighted_blocks, self.next_block, MOMENTARY_BLOCKS);
+                let channel_short_term = window_mean(&state.weighted_blocks, self.next_block, SHORT_TERM_BLOCKS);
+                momentary += weight * channel_momentary;
+                short_term += weight * channel_short_term;
+
+                ChannelLevels {
+                    peak: state.peak,
+                    true_peak: state.true_peak,
+                    rms: window_mean(&state.square_blocks, self.next_block, MOMENTARY_BLOCKS).sqrt() as f32,
+                    momentary_lufs: to_lufs(channel_momentary),
+                    short_term_lufs: to_lufs(channel_short_term),
+                }
+            })
+            .collect();
+
+        MeterSnapshot {
+            sample_rate: self.sample_rate,
+            channels,
+            momentary_lufs: to_lufs(momentary),
+            short_term_lufs: to_lufs(short_term),
+        }
+    }
+
+    fn configure(&mut self, sample_rate: i32, channel_count: usize) {
+        self.sample_rate = sample_rate;
+        self.channels = (0..channel_count).map(|_| ChannelState::new(sample_rate)).collect();
+        self.true_peak_filter = true_peak_filter(true_peak_oversampling(sample_rate));
+        self.block_len = ((sample_rate as usize + 5) / 10).max(1);
+        self.block_pos = 0;
+        self.next_block = 0;
+    }
+
+    fn publish(&self) {
+        let snapshot = self.snapshot();
+        *self.shared.snapshot.lock().unwrap_or_else(PoisonError::into_inner) = snapshot;
+    }
+}
@@ -0,0 +1,261 @@
+//! Band-limited sample rate conversion
+
+use super::{samples_to_ticks, AudioBuffer};
+use crate::receive::AudioFrame;
+use crate::time::{Timecode, Timestamp};
+use std::f64::consts::PI;
+
+/// The number of input samples either side of each output sample used by the filter, when not downsampling
+const HALF_TAPS: usize = 24;
+/// The fraction of the lower of the two Nyquist frequencies that is passed. The rest is the transition band
+const CUTOFF: f64 = 0.92;
+/// The most filter phases to precompute. Ratios needing more use the nearest of this many
+const MAX_PHASES: usize = 1024;
+
+/// The lowest sample rate which can be resampled. Further from the output rate the filter grows without bound
+pub const MIN_SAMPLE_RATE: i32 = 8000;
+/// The highest sample rate which can be resampled
+pub const MAX_SAMPLE_RATE: i32 = 384_000;
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// A Blackman windowed sinc, `x` input samples from its centre, for a filter reaching `half_width` samples either side
+fn kernel(x: f64, cutoff: f64, half_width: f64) -> f64 {
+    if x.abs() >= half_width {
+        return 0.0;
+    }
+    let sinc = if x == 0.0 {
+        1.0
+    } else {
+        (PI * cutoff * x).sin() / (PI * cutoff * x)
+    };
+    let w = PI * (x / half_width + 1.0);
+    let window = 0.42 - 0.5 * w.cos() + 0.08 * (2.0 * w).cos();
+    cutoff * sinc * window
+}
+
+/// Converts audio from any sample rate to a fixed output rate.
+///
+/// The filter state is kept between frames, so a stream of frames is converted without gaps or clicks at the frame
+/// boundaries. Output is delayed by the length of the filter, so each output buffer may hold samples from the end of the
+/// previous frame, and its timecode and timestamp are adjusted to match its first sample. The state is reset if the input
+/// sample rate or number of channels changes, and audio already at the output rate is passed through unchanged. Audio at
+/// any other rate outside `MIN_SAMPLE_RATE` to `MAX_SAMPLE_RATE` is dropped, giving empty channels
+pub struct Resampler {
+    output_rate: i32,
+    input_rate: i32,
+
+    /// The input position of the next output sample is `position + phase / up`, and advances by `down / up`
+    up: usize,
+    down: usize,
+    half_taps: usize,
+    /// The filter taps for each of a number of evenly spaced phases
+    filter: Vec<Vec<f32>>,
+
+    /// The input samples which are still needed, for each channel
+    history: Vec<Vec<f32>>,
+    /// The input position of the first sample in `history`. The stream starts at position zero
+    history_start: i64,
+    position: i64,
+    phase: usize,
+
+    /// The timecode, timestamp and input position of the start of the last input
+    last_input: Option<(Timecode, Timestamp, i64)>,
+}
+impl Resampler {
+    /// Create a resampler producing audio at `output_rate`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `output_rate` is not between `MIN_SAMPLE_RATE` and `MAX_SAMPLE_RATE`
+    pub fn new(output_rate: i32) -> Resampler {
+        assert!(
+            (MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE).contains(&output_rate),
+            "invalid output rate {}",
+            output_rate
+        );
+        Resampler {
+            output_rate,
+            input_rate: 0,
+            up: 1,
+            down: 1,
+            half_taps: 0,
+            filter: Vec::new(),
+            history: Vec::new(),
+            history_start: 0,
+            position: 0,
+            phase: 0,
+            last_input: None,
+        }
+    }
+
+    pub fn output_rate(&self) -> i32 {
+        self.output_rate
+    }
+
+    /// The number of input samples by which the output is delayed
+    pub fn latency(&self) -> usize {
+        self.half_taps
+    }
+
+    /// Resample a received frame
+    pub fn process(&mut self, frame: &AudioFrame) -> AudioBuffer {
+        let data = frame.lock_data();
+        self.resample(
+            &data.channels().collect::<Vec<_>>(),
+            frame.sample_rate,
+            frame.timecode,
+            frame.timestamp,
+        )
+    }
+
+    /// Resample a buffer
+    pub fn apply(&mut self, input: &AudioBuffer) -> AudioBuffer {
+        let channels: Vec<&[f32]> = input.channels.iter().map(|c| &c[..]).collect();
+        self.resample(&channels, input.sample_rate, input.timecode, input.timestamp)
+    }
+
+    /// Produce the output still held back by the filter, as if the input was followed by silence, and reset the state
+    pub fn flush(&mut self) -> AudioBuffer {
+        let res = match self.last_input {
+            Some((timecode, timestamp, start)) if self.input_rate > 0 => {
+                let silence = vec![0.0; self.half_taps];
+                let channels = vec![&silence[..]; self.history.len()];
+                let end = self.history_start + self.history.first().map_or(0, |h| h.len() as i64);
+                // Times relative to the last input, so that the output times carry on from it
+                let offset = (end - start) as f64;
+                let ticks = samples_to_ticks(offset, self.input_rate);
+                let (timecode, timestamp) = (timecode.offset(ticks), timestamp.offset(ticks));
+                self.resample(&channels, self.input_rate, timecode, timestamp)
+            }
+            _ => AudioBuffer {
+                sample_rate: self.output_rate,
+                timecode: Timecode::Synthesize,
+                timestamp: Timestamp::Undefined,
+                channels: Vec::new(),
+            },
+        };
+        self.reset();
+        res
+    }
+
+    fn reset(&mut self) {
+        self.input_rate = 0;
+        self.history.clear();
+        self.last_input = None;
+    }
+
+    fn configure(&mut self, input_rate: i32, channel_count: usize) {
+        let (input, output) = (input_rate as usize, self.output_rate as usize);
+        let divisor = gcd(input, output);
+        let (up, down) = (output / divisor, input / divisor);
+
+        self.input_rate = input_rate;
+        self.up = up;
+        self.down = down;
+        let phases = up.min(MAX_PHASES);
+
+        // When downsampling, lower the cutoff below the output Nyquist frequency, and widen the filter to match
+        let scale = (output as f64 / input as f64).min(1.0);
+        let cutoff = CUTOFF * scale;
+        let half_width = HALF_TAPS as f64 / scale;
+        self.half_taps = half_width.ceil() as usize;
+
+        self.filter = (0..phases)
+            .map(|phase| {
+                let frac = phase as f64 / phases as f64;
+                // Tap j is applied to the input at position - half_taps + 1 + j
+                (0..self.half_taps * 2)
+                    .map(|j| kernel(self.half_taps as f64 - 1.0 - j as f64 + frac, cutoff, half_width) as f32)
+                    .collect()
+            })
+            .collect();
+
+        // Start with silence before the stream, so that the first output sample is at position zero
+        self.history = vec![vec![0.0; self.half_taps - 1]; channel_count];
+        self.history_start = -(self.half_taps as i64 - 1);
+        self.position = 0;
+        self.phase = 0;
+    }
+
+    fn resample(
+        &mut self,
+        inputs: &[&[f32]],
+        sample_rate: i32,
+        timecode: Timecode,
+        timestamp: Timestamp,
+    ) -> AudioBuffer {
+        if sample_rate == self.output_rate {
+            // Nothing to do, and nothing held back
+            self.reset();
+            return AudioBuffer {
+                sample_rate,
+                timecode,
+                timestamp,
+                channels: inputs.iter().map(|c| c.to_vec()).collect(),
+            };
+        }
+        if !(MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE).contains(&sample_rate) {
+            return AudioBuffer {
+                sample_rate: self.output_rate,
+                timecode,
+                timestamp,
+                channels: vec![Vec::new(); inputs.len()],
+            };
+        }
+        if sample_rate != self.input_rate || inputs.len() != self.history.len() {
+            self.configure(sample_rate, inputs.len());
+        }
+
+        let sample_count = inputs.iter().map(|c| c.len()).min().unwrap_or(0);
+        let input_start = self.history_start + self.history.first().map_or(0, |h| h.len() as i64);
+        for (history, input) in self.history.iter_mut().zip(inputs) {
+            history.extend_from_slice(&input[..sample_count]);
+        }
+        let available = self.history.first().map_or(0, |h| h.len());
+
+        // The output starts at the position of its first sample, which may be before the start of this input
+        let first = (self.position - input_start) as f64 + self.phase as f64 / self.up as f64;
+        let ticks = samples_to_ticks(first, sample_rate);
+        let (res_timecode, res_timestamp) = (timecode.offset(ticks), timestamp.offset(ticks));
+        self.last_input = Some((timecode, timestamp, input_start));
+
+        let mut outputs = vec![Vec::new(); self.history.len()];
+        loop {
+            // The taps reach from position - half_taps + 1 to position + half_taps
+            let start = (self.position - self.history_start) as usize + 1 - self.half_taps;
+            if start + self.half_taps * 2 > available {
+                break;
+            }
+            let taps = &self.filter[self.phase * self.filter.len() / self.up];
+            for (output, history) in outputs.iter_mut().zip(&self.history) {
+                let window = &history[start..start + taps.len()];
+                output.push(taps.iter().zip(window).map(|(t, s)| t * s).sum());
+            }
+
+            self.phase += self.down;
+            self.position += (self.phase / self.up) as i64;
+            self.phase %= self.up;
+        }
+
+        // Drop the input which no later output sample needs
+        let keep_from = (self.position + 1 - self.half_taps as i64 - self.history_start).clamp(0, available as i64);
+        for history in &mut self.history {
+            history.drain(..keep_from as usize);
+        }
+        self.history_start += keep_from;
+
+        AudioBuffer {
+            sample_rate: self.output_rate,
+            timecode: res_timecode,
+            timestamp: res_timestamp,
+            channels: outputs,
+        }
+    }
+}
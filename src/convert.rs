@@ -0,0 +1,503 @@
+//! Software conversion between the pixel formats supported by NDI.
+//!
+//! Frames are converted a pair of rows at a time. Each row is decoded to 4:4:4 with 16 bits per channel, converted between
+//! YUV and RGB if needed, then encoded into the destination format. Conversions between two YUV formats, or two RGB formats, never pass through the other
+//! colour space, so are lossless apart from chroma subsampling.
+
+use crate::format::FourCCType;
+use crate::planes::VideoPlanes;
+use crate::receive::VideoFrame;
+use crate::send::FrameBuildError;
+use std::fmt;
+
+/// The matrix used to convert between YUV and RGB
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorMatrix {
+    /// Standard definition video
+    Bt601,
+    /// High definition video
+    Bt709,
+}
+impl ColorMatrix {
+    /// The luma weights of red and blue
+    fn coefficients(self) -> (f32, f32) {
+        match self {
+            ColorMatrix::Bt601 => (0.299, 0.114),
+            ColorMatrix::Bt709 => (0.2126, 0.0722),
+        }
+    }
+}
+
+/// The range of values used by YUV samples
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorRange {
+    /// Luma from 16 to 235, chroma from 16 to 240. This is what NDI uses
+    Limited,
+    /// Luma and chroma use every value
+    Full,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ConvertOptions {
+    pub matrix: ColorMatrix,
+    pub range: ColorRange,
+}
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        ConvertOptions {
+            matrix: ColorMatrix::Bt709,
+            range: ColorRange::Limited,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConvertError {
+    /// The source or destination does not describe a valid frame, for the same reasons a frame would fail to build
+    Frame(FrameBuildError),
+}
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::Frame(e) => e.fmt(f),
+        }
+    }
+}
+impl std::error::Error for ConvertError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConvertError::Frame(e) => Some(e),
+        }
+    }
+}
+impl From<FrameBuildError> for ConvertError {
+    fn from(e: FrameBuildError) -> Self {
+        ConvertError::Frame(e)
+    }
+}
+
+/// A frame produced by `convert` or `convert_frame`, ready to be passed to `NDISendVideoFrameBuilder::with_data`
+pub struct ConvertedFrame {
+    pub data: Vec<u8>,
+    pub width: i32,
+    pub height: i32,
+    pub line_stride: i32,
+    pub format: FourCCType,
+}
+
+/// Convert a received frame into `format`
+pub fn convert_frame(
+    frame: &VideoFrame,
+    format: FourCCType,
+    options: &ConvertOptions,
+) -> Result<ConvertedFrame, ConvertError> {
+    let data = frame.lock_data();
+    convert(
+        &data,
+        frame.four_cc_type,
+        frame.width,
+        frame.height,
+        frame.line_stride_in_bytes,
+        format,
+        options,
+    )
+}
+
+/// Convert a frame into `format`, using the smallest line stride possible for the output
+pub fn convert(
+    src: &[u8],
+    src_format: FourCCType,
+    width: i32,
+    height: i32,
+    src_stride: i32,
+    format: FourCCType,
+    options: &ConvertOptions,
+) -> Result<ConvertedFrame, ConvertError> {
+    let line_stride = format.min_line_stride(width);
+    let size = format.buffer_size(width, height, line_stride)?;
+
+    let mut data = vec![0; size];
+    convert_into(
+        src,
+        src_format,
+        width,
+        height,
+        src_stride,
+        &mut data,
+        format,
+        line_stride,
+        options,
+    )?;
+
+    Ok(ConvertedFrame {
+        data,
+        width,
+        height,
+        line_stride,
+        format,
+    })
+}
+
+/// Convert a frame into `format`, writing it to an existing buffer
+#[allow(clippy::too_many_arguments)]
+pub fn convert_into(
+    src: &[u8],
+    src_format: FourCCType,
+    width: i32,
+    height: i32,
+    src_stride: i32,
+    dst: &mut [u8],
+    format: FourCCType,
+    dst_stride: i32,
+    options: &ConvertOptions,
+) -> Result<(), ConvertError> {
+    let expected = check_buffer(src.len(), src_format, width, height, src_stride)?;
+    check_buffer(dst.len(), format, width, height, dst_stride)?;
+
+    let planes =
+        VideoPlanes::new(src, src_format, width, height, src_stride).ok_or(FrameBuildError::InvalidDataLength {
+            expected,
+            actual: src.len(),
+        })?;
+    let (width, height, dst_stride) = (width as usize, height as usize, dst_stride as usize);
+
+    // Rows are handled in pairs, as that is the smallest unit 4:2:0 chroma can be encoded from
+    let mut rows = [Row::new(width), Row::new(width)];
+    for top in (0..height).step_by(2) {
+        let rows = &mut rows[..(height - top).min(2)];
+        for (i, row) in rows.iter_mut().enumerate() {
+            row.decode(&planes, src_format, top + i);
+            match (row.rgb, format.is_rgb()) {
+                (false, true) => row.yuv_to_rgb(options),
+                (true, false) => row.rgb_to_yuv(options),
+                _ => {}
+            }
+        }
+        encode_rows(dst, format, dst_stride, height, top, rows);
+    }
+    Ok(())
+}
+
+/// Check that a buffer can hold a frame, returning the size it needs
+fn check_buffer(len: usize, four_cc: FourCCType, width: i32, height: i32, stride: i32) -> Result<usize, ConvertError> {
+    let expected = four_cc.buffer_size(width, height, stride)?;
+    if len < expected {
+        Err(FrameBuildError::InvalidDataLength { expected, actual: len }.into())
+    } else {
+        Ok(expected)
+    }
+}
+
+// Samples are held as 16 bit values. YUV samples use the video convention of shifting 8 bit values up, so that the
+// limited range boundaries of each bit depth line up. RGB and alpha samples are scaled to use the full 16 bit range.
+const ALPHA_OPAQUE: u16 = 0xffff;
+
+fn yuv_from_8(v: u8) -> u16 {
+    (v as u16) << 8
+}
+fn yuv_to_8(v: u16) -> u8 {
+    ((v as u32 + 128) >> 8).min(255) as u8
+}
+fn full_from_8(v: u8) -> u16 {
+    v as u16 * 257
+}
+fn full_to_8(v: u16) -> u8 {
+    ((v as u32 + 128) / 257) as u8
+}
+fn average(a: u16, b: u16) -> u16 {
+    (a as u32 + b as u32).div_ceil(2) as u16
+}
+
+/// A row of a frame with every channel at full resolution
+struct Row {
+    /// Whether the channels are R, G, B, A, rather than Y, U, V, A
+    rgb: bool,
+    channels: [Vec<u16>; 4],
+}
+impl Row {
+    fn new(width: usize) -> Row {
+        Row {
+            rgb: false,
+            channels: [
+                vec![0; width],
+                vec![0; width],
+                vec![0; width],
+                vec![ALPHA_OPAQUE; width],
+            ],
+        }
+    }
+
+    /// Decode row `y` of a frame, replacing the contents of this row
+    fn decode(&mut self, planes: &VideoPlanes, four_cc: FourCCType, y: usize) {
+        self.rgb = four_cc.is_rgb();
+        let [c0, c1, c2, c3] = &mut self.channels;
+
+        match (*planes, four_cc) {
+            (VideoPlanes::Packed(plane), FourCCType::UYVY) => decode_uyvy(plane.row(y), c0, c1, c2),
+            (VideoPlanes::Packed(plane), _) => {
+                // Offsets of R, G, B and A within each pixel
+                let (r, g, b, a) = match four_cc {
+                    FourCCType::BGRA | FourCCType::BGRX => (2, 1, 0, 3),
+                    _ => (0, 1, 2, 3),
+                };
+                let alpha = matches!(four_cc, FourCCType::BGRA | FourCCType::RGBA);
+                for (((px, r_out), (g_out, b_out)), a_out) in plane
+                    .row(y)
+                    .chunks_exact(4)
+                    .zip(c0.iter_mut())
+                    .zip(c1.iter_mut().zip(c2.iter_mut()))
+                    .zip(c3.iter_mut())
+                {
+                    *r_out = full_from_8(px[r]);
+                    *g_out = full_from_8(px[g]);
+                    *b_out = full_from_8(px[b]);
+                    if alpha {
+                        *a_out = full_from_8(px[a]);
+                    }
+                }
+            }
+            (VideoPlanes::PackedAlpha { packed, alpha }, _) => {
+                decode_uyvy(packed.row(y), c0, c1, c2);
+                for (a, o) in alpha.row(y).iter().zip(c3.iter_mut()) {
+                    *o = full_from_8(*a);
+                }
+            }
+            (VideoPlanes::Planar { y: luma, u, v }, _) => {
+                decode_luma_8(luma.row(y), c0);
+                let chroma = u.row(y / 2).iter().zip(v.row(y / 2));
+                for ((u, v), (u_out, v_out)) in chroma.zip(c1.chunks_exact_mut(2).zip(c2.chunks_exact_mut(2))) {
+                    u_out.fill(yuv_from_8(*u));
+                    v_out.fill(yuv_from_8(*v));
+                }
+            }
+            (VideoPlanes::SemiPlanar { y: luma, uv }, FourCCType::NV12) => {
+                decode_luma_8(luma.row(y), c0);
+                let chroma = uv.row(y / 2).chunks_exact(2);
+                for (pair, (u_out, v_out)) in chroma.zip(c1.chunks_exact_mut(2).zip(c2.chunks_exact_mut(2))) {
+                    u_out.fill(yuv_from_8(pair[0]));
+                    v_out.fill(yuv_from_8(pair[1]));
+                }
+            }
+            (VideoPlanes::SemiPlanar { y: luma, uv }, _) => decode_p216(luma.row(y), uv.row(y), c0, c1, c2),
+            (VideoPlanes::SemiPlanarAlpha { y: luma, uv, alpha }, _) => {
+                decode_p216(luma.row(y), uv.row(y), c0, c1, c2);
+                for (a, o) in alpha.row(y).chunks_exact(2).zip(c3.iter_mut()) {
+                    *o = u16::from_le_bytes([a[0], a[1]]);
+                }
+            }
+        }
+    }
+
+    /// The average chroma of each horizontal pair of pixels
+    fn chroma_422(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        let [_, u, v, _] = &self.channels;
+        u.chunks_exact(2)
+            .zip(v.chunks_exact(2))
+            .map(|(u, v)| (average(u[0], u[1]), average(v[0], v[1])))
+    }
+
+    fn yuv_to_rgb(&mut self, options: &ConvertOptions) {
+        let (kr, kb) = options.matrix.coefficients();
+        let kg = 1.0 - kr - kb;
+        let (y_offset, y_scale, c_scale) = yuv_levels(options.range);
+        let rgb_scale = 65535.0;
+
+        // Fold the range scaling into the matrix, so the inner loop is a few multiply-adds per pixel
+        let y_mul = rgb_scale / y_scale;
+        let r_v = 2.0 * (1.0 - kr) * rgb_scale / c_scale;
+        let b_u = 2.0 * (1.0 - kb) * rgb_scale / c_scale;
+        let g_u = -b_u * kb / kg;
+        let g_v = -r_v * kr / kg;
+
+        let [c0, c1, c2, _] = &mut self.channels;
+        for ((y, u), v) in c0.iter_mut().zip(c1.iter_mut()).zip(c2.iter_mut()) {
+            let luma = (*y as f32 - y_offset) * y_mul;
+            let pb = *u as f32 - 32768.0;
+            let pr = *v as f32 - 32768.0;
+            *y = to_u16(luma + r_v * pr);
+            *u = to_u16(luma + g_u * pb + g_v * pr);
+            *v = to_u16(luma + b_u * pb);
+        }
+        self.rgb = true;
+    }
+
+    fn rgb_to_yuv(&mut self, options: &ConvertOptions) {
+        let (kr, kb) = options.matrix.coefficients();
+        let kg = 1.0 - kr - kb;
+        let (y_offset, y_scale, c_scale) = yuv_levels(options.range);
+        let rgb_scale = 65535.0;
+
+        let (y_r, y_g, y_b) = (
+            kr * y_scale / rgb_scale,
+            kg * y_scale / rgb_scale,
+            kb * y_scale / rgb_scale,
+        );
+        let u_scale = c_scale / (2.0 * (1.0 - kb) * rgb_scale);
+        let v_scale = c_scale / (2.0 * (1.0 - kr) * rgb_scale);
+        let (u_r, u_g, u_b) = (-kr * u_scale, -kg * u_scale, (1.0 - kb) * u_scale);
+        let (v_r, v_g, v_b) = ((1.0 - kr) * v_scale, -kg * v_scale, -kb * v_scale);
+
+        let [c0, c1, c2, _] = &mut self.channels;
+        for ((r, g), b) in c0.iter_mut().zip(c1.iter_mut()).zip(c2.iter_mut()) {
+            let (rf, gf, bf) = (*r as f32, *g as f32, *b as f32);
+            *r = to_u16(y_offset + y_r * rf + y_g * gf + y_b * bf);
+            *g = to_u16(32768.0 + u_r * rf + u_g * gf + u_b * bf);
+            *b = to_u16(32768.0 + v_r * rf + v_g * gf + v_b * bf);
+        }
+        self.rgb = false;
+    }
+}
+
+/// The average chroma of each 2x2 block of pixels, from a pair of rows. The last row of a frame with an odd height is
+/// passed as both rows, so it is used alone
+fn chroma_420<'a>(top: &'a Row, bottom: &'a Row) -> impl Iterator<Item = (u16, u16)> + 'a {
+    top.chroma_422()
+        .zip(bottom.chroma_422())
+        .map(|((u0, v0), (u1, v1))| (average(u0, u1), average(v0, v1)))
+}
+
+/// The luma offset, luma scale and chroma scale, in 16 bit sample values
+fn yuv_levels(range: ColorRange) -> (f32, f32, f32) {
+    match range {
+        ColorRange::Limited => (16.0 * 256.0, 219.0 * 256.0, 224.0 * 256.0),
+        ColorRange::Full => (0.0, 255.0 * 256.0, 255.0 * 256.0),
+    }
+}
+
+fn to_u16(v: f32) -> u16 {
+    (v + 0.5).clamp(0.0, 65535.0) as u16
+}
+
+fn decode_uyvy(src: &[u8], y_out: &mut [u16], u_out: &mut [u16], v_out: &mut [u16]) {
+    let out = y_out
+        .chunks_exact_mut(2)
+        .zip(u_out.chunks_exact_mut(2).zip(v_out.chunks_exact_mut(2)));
+    for (px, (y, (u, v))) in src.chunks_exact(4).zip(out) {
+        y[0] = yuv_from_8(px[1]);
+        y[1] = yuv_from_8(px[3]);
+        u.fill(yuv_from_8(px[0]));
+        v.fill(yuv_from_8(px[2]));
+    }
+}
+
+fn decode_luma_8(src: &[u8], out: &mut [u16]) {
+    for (s, d) in src.iter().zip(out) {
+        *d = yuv_from_8(*s);
+    }
+}
+
+fn decode_p216(luma: &[u8], chroma: &[u8], y_out: &mut [u16], u_out: &mut [u16], v_out: &mut [u16]) {
+    for (s, d) in luma.chunks_exact(2).zip(y_out) {
+        *d = u16::from_le_bytes([s[0], s[1]]);
+    }
+    for (s, (u, v)) in chroma
+        .chunks_exact(4)
+        .zip(u_out.chunks_exact_mut(2).zip(v_out.chunks_exact_mut(2)))
+    {
+        u.fill(u16::from_le_bytes([s[0], s[1]]));
+        v.fill(u16::from_le_bytes([s[2], s[3]]));
+    }
+}
+
+/// Encode `rows`, which start at row `top` of the frame, into `dst`. There are two rows, apart from the last row of a
+/// frame with an odd height
+fn encode_rows(dst: &mut [u8], four_cc: FourCCType, stride: usize, height: usize, top: usize, rows: &[Row]) {
+    let width = rows[0].channels[0].len();
+    // The size of a full height plane
+    let plane = stride * height;
+
+    match four_cc {
+        FourCCType::BGRA | FourCCType::BGRX | FourCCType::RGBA | FourCCType::RGBX => {
+            let (r, g, b, a) = match four_cc {
+                FourCCType::BGRA | FourCCType::BGRX => (2, 1, 0, 3),
+                _ => (0, 1, 2, 3),
+            };
+            let alpha = matches!(four_cc, FourCCType::BGRA | FourCCType::RGBA);
+            for (i, row) in rows.iter().enumerate() {
+                let [c0, c1, c2, c3] = &row.channels;
+                let out = dst[(top + i) * stride..][..width * 4].chunks_exact_mut(4);
+                for (px, ((r_in, g_in), (b_in, a_in))) in out.zip(c0.iter().zip(c1).zip(c2.iter().zip(c3))) {
+                    px[r] = full_to_8(*r_in);
+                    px[g] = full_to_8(*g_in);
+                    px[b] = full_to_8(*b_in);
+                    px[a] = if alpha { full_to_8(*a_in) } else { 255 };
+                }
+            }
+        }
+        FourCCType::UYVY | FourCCType::UYVA => {
+            for (i, row) in rows.iter().enumerate() {
+                encode_uyvy(row, &mut dst[(top + i) * stride..][..width * 2]);
+                if four_cc == FourCCType::UYVA {
+                    let out = &mut dst[plane + (top + i) * width..][..width];
+                    for (a, o) in row.channels[3].iter().zip(out) {
+                        *o = full_to_8(*a);
+                    }
+                }
+            }
+        }
+        FourCCType::I420 | FourCCType::YV12 | FourCCType::NV12 => {
+            for (i, row) in rows.iter().enumerate() {
+                encode_luma_8(row, &mut dst[(top + i) * stride..][..width]);
+            }
+            let chroma = chroma_420(&rows[0], &rows[rows.len() - 1]);
+            let cy = top / 2;
+            if four_cc == FourCCType::NV12 {
+                let out = dst[plane + cy * stride..][..width].chunks_exact_mut(2);
+                for (o, (u, v)) in out.zip(chroma) {
+                    o[0] = yuv_to_8(u);
+                    o[1] = yuv_to_8(v);
+                }
+            } else {
+                let chroma_stride = stride / 2;
+                let (first, second) = dst[plane..].split_at_mut(chroma_stride * height.div_ceil(2));
+                let (u_plane, v_plane) = if four_cc == FourCCType::I420 {
+                    (first, second)
+                } else {
+                    (second, first)
+                };
+                let u_out = &mut u_plane[cy * chroma_stride..][..width / 2];
+                let v_out = &mut v_plane[cy * chroma_stride..][..width / 2];
+                for ((u_o, v_o), (u, v)) in u_out.iter_mut().zip(v_out.iter_mut()).zip(chroma) {
+                    *u_o = yuv_to_8(u);
+                    *v_o = yuv_to_8(v);
+                }
+            }
+        }
+        FourCCType::P216 | FourCCType::PA16 => {
+            for (i, row) in rows.iter().enumerate() {
+                let offset = (top + i) * stride;
+                let luma = dst[offset..][..width * 2].chunks_exact_mut(2);
+                for (o, s) in luma.zip(&row.channels[0]) {
+                    o.copy_from_slice(&s.to_le_bytes());
+                }
+                let chroma = dst[plane + offset..][..width * 2].chunks_exact_mut(4);
+                for (o, (u, v)) in chroma.zip(row.chroma_422()) {
+                    o[..2].copy_from_slice(&u.to_le_bytes());
+                    o[2..].copy_from_slice(&v.to_le_bytes());
+                }
+                if four_cc == FourCCType::PA16 {
+                    let alpha = dst[plane * 2 + offset..][..width * 2].chunks_exact_mut(2);
+                    for (o, a) in alpha.zip(&row.channels[3]) {
+                        o.copy_from_slice(&a.to_le_bytes());
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn encode_uyvy(row: &Row, dst: &mut [u8]) {
+    let luma = row.channels[0].chunks_exact(2);
+    for ((px, y), (u, v)) in dst.chunks_exact_mut(4).zip(luma).zip(row.chroma_422()) {
+        px[0] = yuv_to_8(u);
+        px[1] = yuv_to_8(y[0]);
+        px[2] = yuv_to_8(v);
+        px[3] = yuv_to_8(y[1]);
+    }
+}
+
+fn encode_luma_8(row: &Row, dst: &mut [u8]) {
+    for (d, s) in dst.iter_mut().zip(&row.channels[0]) {
+        *d = yuv_to_8(*s);
+    }
+}
@@ -10,7 +10,9 @@ pub struct FindSource {
     pub url: Option<String>,
 }
 
+// The list returned by the SDK is freed by the next call to get it, so a finder must not be used from two threads at once
 unsafe impl Send for FindInstance {}
+/// A finder. It can be moved to another thread, but not shared between threads
 pub struct FindInstance {
     sdk_instance: Arc<NDIHandle>,
     instance: sdk::NDIlib_find_instance_t,
@@ -0,0 +1,273 @@
+//! The video formats understood by NDI, shared by senders and receivers
+
+use crate::sdk;
+use crate::send::FrameBuildError;
+use crate::time::FrameRate;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatError {
+    /// The value is not one of the SDK's FourCC types
+    UnknownFourCC(u32),
+    /// The value is not one of the SDK's frame format types
+    UnknownFrameFormat(u32),
+    /// The string is not the name of a supported FourCC
+    InvalidFourCC(String),
+}
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::UnknownFourCC(v) => write!(f, "unknown FourCC {:#010x}", v),
+            FormatError::UnknownFrameFormat(v) => write!(f, "unknown frame format type {}", v),
+            FormatError::InvalidFourCC(s) => write!(f, "{:?} is not a supported FourCC", s),
+        }
+    }
+}
+impl std::error::Error for FormatError {}
+
+/// The properties of a video stream which a renderer depends on, so which need it to reallocate when they change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VideoFormat {
+    pub width: i32,
+    pub height: i32,
+    pub frame_rate: FrameRate,
+    pub four_cc_type: FourCCType,
+    pub frame_format_type: FrameFormatType,
+}
+impl fmt::Display for VideoFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}x{} {} at {} fps, {}",
+            self.width, self.height, self.four_cc_type, self.frame_rate, self.frame_format_type
+        )
+    }
+}
+
+/// How the lines of a frame make up the picture
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum FrameFormatType {
+    Progressive = sdk::NDIlib_frame_format_type_progressive as isize,
+    /// Both fields, with the lines of each interleaved
+    Interleaved = sdk::NDIlib_frame_format_type_interleaved as isize,
+    /// Only the even lines
+    Field0 = sdk::NDIlib_frame_format_type_field_0 as isize,
+    /// Only the odd lines
+    Field1 = sdk::NDIlib_frame_format_type_field_1 as isize,
+}
+impl TryFrom<u32> for FrameFormatType {
+    type Error = FormatError;
+
+    fn try_from(v: u32) -> Result<Self, Self::Error> {
+        match v {
+            x if x == FrameFormatType::Progressive as u32 => Ok(FrameFormatType::Progressive),
+            x if x == FrameFormatType::Interleaved as u32 => Ok(FrameFormatType::Interleaved),
+            x if x == FrameFormatType::Field0 as u32 => Ok(FrameFormatType::Field0),
+            x if x == FrameFormatType::Field1 as u32 => Ok(FrameFormatType::Field1),
+            _ => Err(FormatError::UnknownFrameFormat(v)),
+        }
+    }
+}
+impl From<FrameFormatType> for u32 {
+    fn from(v: FrameFormatType) -> u32 {
+        v as u32
+    }
+}
+impl fmt::Display for FrameFormatType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FrameFormatType::Progressive => "progressive",
+            FrameFormatType::Interleaved => "interleaved",
+            FrameFormatType::Field0 => "field 0",
+            FrameFormatType::Field1 => "field 1",
+        })
+    }
+}
+
+/// The subsampling of the chroma relative to the luma. Formats without chroma are `Cs444`
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum ChromaSubsampling {
+    /// Full resolution chroma
+    Cs444,
+    /// Half the horizontal resolution
+    Cs422,
+    /// Half the horizontal and vertical resolution
+    Cs420,
+}
+impl fmt::Display for ChromaSubsampling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChromaSubsampling::Cs444 => "4:4:4",
+            ChromaSubsampling::Cs422 => "4:2:2",
+            ChromaSubsampling::Cs420 => "4:2:0",
+        })
+    }
+}
+
+// The bindings are generated from a header which predates the 16 bit formats, so their codes are defined here the way
+// the SDK defines every FourCC, as the four characters read as a little endian integer
+const FOURCC_P216: u32 = u32::from_le_bytes(*b"P216");
+const FOURCC_PA16: u32 = u32::from_le_bytes(*b"PA16");
+
+/// The pixel format of a frame. Displays, and parses, as its four characters
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum FourCCType {
+    UYVY = sdk::NDIlib_FourCC_type_UYVY as isize,
+    UYVA = sdk::NDIlib_FourCC_type_UYVA as isize,
+    P216 = FOURCC_P216 as isize,
+    PA16 = FOURCC_PA16 as isize,
+    YV12 = sdk::NDIlib_FourCC_type_YV12 as isize,
+    I420 = sdk::NDIlib_FourCC_type_I420 as isize,
+    NV12 = sdk::NDIlib_FourCC_type_NV12 as isize,
+    BGRA = sdk::NDIlib_FourCC_type_BGRA as isize,
+    BGRX = sdk::NDIlib_FourCC_type_BGRX as isize,
+    RGBA = sdk::NDIlib_FourCC_type_RGBA as isize,
+    RGBX = sdk::NDIlib_FourCC_type_RGBX as isize,
+}
+impl TryFrom<u32> for FourCCType {
+    type Error = FormatError;
+
+    fn try_from(v: u32) -> Result<Self, Self::Error> {
+        match v {
+            x if x == FourCCType::UYVY as u32 => Ok(FourCCType::UYVY),
+            x if x == FourCCType::UYVA as u32 => Ok(FourCCType::UYVA),
+            x if x == FourCCType::P216 as u32 => Ok(FourCCType::P216),
+            x if x == FourCCType::PA16 as u32 => Ok(FourCCType::PA16),
+            x if x == FourCCType::YV12 as u32 => Ok(FourCCType::YV12),
+            x if x == FourCCType::I420 as u32 => Ok(FourCCType::I420),
+            x if x == FourCCType::NV12 as u32 => Ok(FourCCType::NV12),
+            x if x == FourCCType::BGRA as u32 => Ok(FourCCType::BGRA),
+            x if x == FourCCType::BGRX as u32 => Ok(FourCCType::BGRX),
+            x if x == FourCCType::RGBA as u32 => Ok(FourCCType::RGBA),
+            x if x == FourCCType::RGBX as u32 => Ok(FourCCType::RGBX),
+            _ => Err(FormatError::UnknownFourCC(v)),
+        }
+    }
+}
+impl From<FourCCType> for u32 {
+    fn from(v: FourCCType) -> u32 {
+        v as u32
+    }
+}
+impl fmt::Display for FourCCType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // A FourCC is its four characters packed into a little endian integer
+        for c in (*self as u32).to_le_bytes().iter() {
+            write!(f, "{}", *c as char)?;
+        }
+        Ok(())
+    }
+}
+impl FromStr for FourCCType {
+    type Err = FormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = s.as_bytes();
+        if bytes.len() != 4 {
+            return Err(FormatError::InvalidFourCC(s.to_string()));
+        }
+        let v = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        FourCCType::try_from(v).map_err(|_| FormatError::InvalidFourCC(s.to_string()))
+    }
+}
+impl FourCCType {
+    /// Whether the format carries an alpha channel
+    pub fn has_alpha(self) -> bool {
+        matches!(
+            self,
+            FourCCType::UYVA | FourCCType::PA16 | FourCCType::BGRA | FourCCType::RGBA
+        )
+    }
+
+    /// Whether the format is RGB, rather than YUV
+    pub fn is_rgb(self) -> bool {
+        matches!(
+            self,
+            FourCCType::BGRA | FourCCType::BGRX | FourCCType::RGBA | FourCCType::RGBX
+        )
+    }
+
+    /// Whether the frame is split into more than one plane
+    pub fn is_planar(self) -> bool {
+        !matches!(
+            self,
+            FourCCType::UYVY | FourCCType::BGRA | FourCCType::BGRX | FourCCType::RGBA | FourCCType::RGBX
+        )
+    }
+
+    /// The average number of bits used by each pixel, across every plane
+    pub fn bits_per_pixel(self) -> u32 {
+        match self {
+            FourCCType::YV12 | FourCCType::I420 | FourCCType::NV12 => 12,
+            FourCCType::UYVY => 16,
+            FourCCType::UYVA => 24,
+            FourCCType::P216 | FourCCType::BGRA | FourCCType::BGRX | FourCCType::RGBA | FourCCType::RGBX => 32,
+            FourCCType::PA16 => 48,
+        }
+    }
+
+    /// How the chroma is subsampled, relative to the luma
+    pub fn chroma_subsampling(self) -> ChromaSubsampling {
+        match self {
+            FourCCType::UYVY | FourCCType::UYVA | FourCCType::P216 | FourCCType::PA16 => ChromaSubsampling::Cs422,
+            FourCCType::YV12 | FourCCType::I420 | FourCCType::NV12 => ChromaSubsampling::Cs420,
+            FourCCType::BGRA | FourCCType::BGRX | FourCCType::RGBA | FourCCType::RGBX => ChromaSubsampling::Cs444,
+        }
+    }
+
+    /// The smallest line stride which can hold a line of `width` pixels. For planar formats this is of the luma plane.
+    /// Saturates at `i32::MAX` if the line is too long for any stride to hold
+    pub fn min_line_stride(self, width: i32) -> i32 {
+        self.checked_min_line_stride(width).unwrap_or(i32::MAX)
+    }
+
+    fn checked_min_line_stride(self, width: i32) -> Option<i32> {
+        match self {
+            FourCCType::UYVY | FourCCType::UYVA => width.checked_mul(2),
+            FourCCType::P216 | FourCCType::PA16 => width.checked_mul(2),
+            FourCCType::YV12 | FourCCType::I420 | FourCCType::NV12 => Some(width),
+            FourCCType::BGRA | FourCCType::BGRX | FourCCType::RGBA | FourCCType::RGBX => width.checked_mul(4),
+        }
+    }
+
+    /// The number of bytes needed to hold a frame in this format, including every plane.
+    ///
+    /// * `UYVY` and the RGB formats are a single plane of `line_stride * height` bytes
+    /// * `YV12` and `I420` are a luma plane, followed by two chroma planes of half the stride and height
+    /// * `NV12` is a luma plane, followed by an interleaved chroma plane of the same stride and half the height
+    /// * `UYVA` is a `UYVY` plane, followed by an alpha plane of `width * height` bytes
+    /// * `P216` is a 16 bit luma plane, followed by an interleaved 16 bit chroma plane, both of the same stride and height
+    /// * `PA16` is a `P216` frame, followed by a 16 bit alpha plane of the same stride and height
+    ///
+    /// Dimensions too large for the size of a line or the frame to be represented are invalid
+    pub fn buffer_size(self, width: i32, height: i32, line_stride: i32) -> Result<usize, FrameBuildError> {
+        let invalid = FrameBuildError::InvalidDimensions { width, height };
+        let subsampled = self.chroma_subsampling() != ChromaSubsampling::Cs444;
+        if width <= 0 || height <= 0 || (subsampled && width % 2 != 0) {
+            return Err(invalid);
+        }
+        let minimum = self.checked_min_line_stride(width).ok_or_else(|| invalid.clone())?;
+        if line_stride < minimum {
+            return Err(FrameBuildError::InvalidStride { line_stride, minimum });
+        }
+
+        let (width, height, line_stride) = (width as usize, height as usize, line_stride as usize);
+        let chroma_height = height.div_ceil(2);
+        let plane = line_stride.checked_mul(height);
+        match self {
+            FourCCType::UYVY | FourCCType::BGRA | FourCCType::BGRX | FourCCType::RGBA | FourCCType::RGBX => plane,
+            FourCCType::YV12 | FourCCType::I420 => (line_stride / 2)
+                .checked_mul(chroma_height * 2)
+                .and_then(|chroma| plane?.checked_add(chroma)),
+            FourCCType::NV12 => line_stride
+                .checked_mul(chroma_height)
+                .and_then(|chroma| plane?.checked_add(chroma)),
+            FourCCType::UYVA => width.checked_mul(height).and_then(|alpha| plane?.checked_add(alpha)),
+            FourCCType::P216 => plane.and_then(|plane| plane.checked_mul(2)),
+            FourCCType::PA16 => plane.and_then(|plane| plane.checked_mul(3)),
+        }
+        .ok_or(invalid)
+    }
+}
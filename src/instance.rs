@@ -1,131 +1,319 @@
-pub use self::internal::{load, NDIHandle};
-
-#[cfg(not(feature = "dynamic-link"))]
-mod internal {
-    use crate::{sdk, NDIInstance};
-    use std::ops::Deref;
-    use std::sync::Arc;
+pub use self::internal::load;
+#[cfg(feature = "dynamic-link")]
+pub use self::internal::{load_with, LibrarySearch};
+use crate::{sdk, NDIInstance};
+use std::fmt;
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
 
-    unsafe impl Send for NDIHandle {}
-    pub struct NDIHandle {
-        instance: sdk::NDIlib_v3,
+#[derive(Debug)]
+pub enum LoadError {
+    /// None of the candidate paths could be opened. Contains every path tried, along with why it failed
+    NotFound(Vec<(PathBuf, String)>),
+    /// The library was opened, but is not a usable NDI runtime
+    Invalid(String),
+    /// The runtime failed to initialise
+    InitFailed,
+}
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::NotFound(attempts) => {
+                write!(f, "failed to find the NDI runtime")?;
+                for (i, (path, reason)) in attempts.iter().enumerate() {
+                    write!(f, "{} {}: {}", if i == 0 { ":" } else { ";" }, path.display(), reason)?;
+                }
+                Ok(())
+            }
+            LoadError::Invalid(reason) => write!(f, "the NDI runtime is not usable: {}", reason),
+            LoadError::InitFailed => write!(f, "NDIlib_initialize failed"),
+        }
     }
-    impl Deref for NDIHandle {
-        type Target = sdk::NDIlib_v3;
+}
+impl std::error::Error for LoadError {}
 
-        fn deref(&self) -> &sdk::NDIlib_v3 {
-            &self.instance
+/// The process wide runtime, shared by every `NDIInstance`
+struct Runtime {
+    /// The handle currently responsible for calling `NDIlib_destroy`
+    handle: Weak<NDIHandle>,
+    instance: sdk::NDIlib_v3,
+    // Dropped after `NDIlib_destroy` has been called
+    _library: internal::Library,
+}
+
+static RUNTIME: Mutex<Option<Runtime>> = Mutex::new(None);
+
+fn lock_runtime() -> MutexGuard<'static, Option<Runtime>> {
+    // The runtime state is only modified once the SDK calls have succeeded, so is valid even after a panic
+    RUNTIME.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Return the loaded runtime, or load and initialise it with `open` if there is none.
+fn acquire<F>(open: F) -> Result<NDIInstance, LoadError>
+where
+    F: FnOnce() -> Result<(sdk::NDIlib_v3, internal::Library), LoadError>,
+{
+    let mut runtime = lock_runtime();
+
+    if let Some(runtime) = runtime.as_mut() {
+        if let Some(handle) = runtime.handle.upgrade() {
+            return Ok(NDIInstance { handle });
         }
+
+        // The last handle is being dropped, but has not yet destroyed the SDK. Take over responsibility for that, rather
+        // than initialising again before the destroy
+        let handle = Arc::new(NDIHandle {
+            instance: runtime.instance,
+        });
+        runtime.handle = Arc::downgrade(&handle);
+        return Ok(NDIInstance { handle });
+    }
+
+    let (instance, library) = open()?;
+    if !unsafe { instance.NDIlib_initialize.unwrap()() } {
+        return Err(LoadError::InitFailed);
     }
-    impl Drop for NDIHandle {
-        fn drop(&mut self) {
+
+    let handle = Arc::new(NDIHandle { instance });
+    *runtime = Some(Runtime {
+        handle: Arc::downgrade(&handle),
+        instance,
+        _library: library,
+    });
+    Ok(NDIInstance { handle })
+}
+
+/// A reference to the loaded runtime.
+///
+/// Every finder, sender and receiver holds one of these, so `NDIlib_destroy` is only called once all of them, along with
+/// every `NDIInstance`, have been dropped.
+pub struct NDIHandle {
+    instance: sdk::NDIlib_v3,
+}
+// The runtime is a table of function pointers, which the SDK allows to be called from any thread
+unsafe impl Send for NDIHandle {}
+unsafe impl Sync for NDIHandle {}
+impl Deref for NDIHandle {
+    type Target = sdk::NDIlib_v3;
+
+    fn deref(&self) -> &sdk::NDIlib_v3 {
+        &self.instance
+    }
+}
+impl Drop for NDIHandle {
+    fn drop(&mut self) {
+        let mut runtime = lock_runtime();
+
+        // Another handle may have taken over the runtime while this one was waiting for the lock
+        let current = runtime
+            .as_ref()
+            .is_some_and(|r| std::ptr::eq(r.handle.as_ptr(), self as *const NDIHandle));
+        if current {
             unsafe {
                 self.NDIlib_destroy.unwrap()();
             }
+            *runtime = None;
         }
     }
+}
+
+#[cfg(not(feature = "dynamic-link"))]
+mod internal {
+    use super::{acquire, LoadError};
+    use crate::{sdk, NDIInstance};
+
+    /// The runtime is linked in, so there is no library to hold open
+    pub type Library = ();
 
     /// Initialise an instance of the SDK
     ///
-    /// # Arguments
+    /// The runtime is shared by the whole process, so this can be called any number of times. It is initialised by the
+    /// first call, and destroyed once every `NDIInstance` and everything created from them has been dropped.
     ///
-    /// When the feature 'dynamic-link' is enabled, this method has a different signature of `pub fn load(custom_path: Option<String>) -> Result<NDIInstance, String>`
-    /// This allows for loading from a custom path
+    /// When the feature 'dynamic-link' is enabled, `load_with` is also available to control where the library is
+    /// searched for, or to load it from a custom path
     ///
     /// # Returns
     ///
     /// Returns a NDIInstance when successful.
     ///
-    pub fn load() -> Result<NDIInstance, String> {
-        let instance = unsafe { sdk::NDIlib_v3_load().as_ref() };
-        match instance {
-            None => Err("Failed to load lib".to_string()),
-            Some(inst) => {
-                if unsafe { inst.NDIlib_initialize.unwrap()() } {
-                    Ok(NDIInstance {
-                        handle: Arc::new(NDIHandle { instance: *inst }),
-                    })
-                } else {
-                    Err("Init returned false".to_string())
-                }
+    pub fn load() -> Result<NDIInstance, LoadError> {
+        acquire(|| {
+            let instance = unsafe { sdk::NDIlib_v3_load().as_ref() };
+            match instance {
+                None => Err(LoadError::Invalid("NDIlib_v3_load returned null".to_string())),
+                Some(inst) => Ok((*inst, ())),
             }
-        }
+        })
     }
 }
 
 #[cfg(feature = "dynamic-link")]
 mod internal {
+    use super::{acquire, LoadError};
     use crate::{sdk, NDIInstance};
-    use libloading::{Library, Symbol};
+    pub use libloading::Library;
+    use libloading::Symbol;
     use std::env;
-    use std::ops::Deref;
-    use std::path::Path;
-    use std::sync::Arc;
-
-    unsafe impl Send for NDIHandle {}
-    pub struct NDIHandle {
-        _handle: Option<Library>,
-        instance: sdk::NDIlib_v3,
-    }
-    impl Deref for NDIHandle {
-        type Target = sdk::NDIlib_v3;
+    use std::path::PathBuf;
 
-        fn deref(&self) -> &sdk::NDIlib_v3 {
-            &self.instance
-        }
+    /// Runtime library file names, newest first
+    #[cfg(target_os = "windows")]
+    const LIBRARY_NAMES: &[&str] = &["Processing.NDI.Lib.x64.dll"];
+    #[cfg(not(target_os = "windows"))]
+    const LIBRARY_NAMES: &[&str] = &["libndi.so", "libndi.so.5", "libndi.so.4", "libndi.so.3"];
+
+    /// Environment variables set by the runtime installers, newest first
+    const RUNTIME_ENV_VARS: &[&str] = &["NDI_RUNTIME_DIR_V5", "NDI_RUNTIME_DIR_V4", "NDI_RUNTIME_DIR_V3"];
+
+    /// Well known install directories for the runtime
+    #[cfg(target_os = "windows")]
+    const SYSTEM_DIRS: &[&str] = &[];
+    #[cfg(not(target_os = "windows"))]
+    const SYSTEM_DIRS: &[&str] = &["/usr/lib/ndi", "/usr/local/lib/ndi"];
+
+    /// Where to look for the NDI runtime library
+    ///
+    /// The default search tries, in order:
+    /// * Any paths added with `with_path`
+    /// * The current directory
+    /// * The directories named by `NDI_RUNTIME_DIR_V5`, `NDI_RUNTIME_DIR_V4` and `NDI_RUNTIME_DIR_V3`
+    /// * `/usr/lib/ndi` and `/usr/local/lib/ndi`
+    /// * The system default search paths
+    ///
+    /// In each directory, `libndi.so` is tried first, followed by the versioned sonames `libndi.so.5`, `libndi.so.4` and
+    /// `libndi.so.3`.
+    #[derive(Debug, Clone)]
+    pub struct LibrarySearch {
+        paths: Vec<PathBuf>,
+        current_dir: bool,
+        env_vars: bool,
+        system_dirs: bool,
+        system_default: bool,
     }
-    impl Drop for NDIHandle {
-        fn drop(&mut self) {
-            unsafe {
-                self.NDIlib_destroy.unwrap()();
+    impl Default for LibrarySearch {
+        fn default() -> Self {
+            LibrarySearch {
+                paths: Vec::new(),
+                current_dir: true,
+                env_vars: true,
+                system_dirs: true,
+                system_default: true,
             }
         }
     }
+    impl LibrarySearch {
+        /// A search that only tries the paths added with `with_path`
+        pub fn empty() -> Self {
+            LibrarySearch {
+                paths: Vec::new(),
+                current_dir: false,
+                env_vars: false,
+                system_dirs: false,
+                system_default: false,
+            }
+        }
+        /// Try a path before any of the default locations. This can be either the library file, or a directory containing it
+        pub fn with_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+            self.paths.push(path.into());
+            self
+        }
+        pub fn with_current_dir(mut self, enabled: bool) -> Self {
+            self.current_dir = enabled;
+            self
+        }
+        pub fn with_env_vars(mut self, enabled: bool) -> Self {
+            self.env_vars = enabled;
+            self
+        }
+        pub fn with_system_dirs(mut self, enabled: bool) -> Self {
+            self.system_dirs = enabled;
+            self
+        }
+        pub fn with_system_default(mut self, enabled: bool) -> Self {
+            self.system_default = enabled;
+            self
+        }
+
+        /// Every path that will be tried, in order
+        pub fn candidates(&self) -> Vec<PathBuf> {
+            let mut dirs = Vec::new();
+            let mut res = Vec::new();
 
-    pub fn load(custom_path: Option<String>) -> Result<NDIInstance, String> {
-        let lib_path = if let Some(path) = custom_path {
-            path
-        } else {
-            let local_path = "./libndi.so";
-            if Path::new(local_path).exists() {
-                local_path.to_string()
-            } else if let Ok(env_var) = env::var("NDI_RUNTIME_DIR_V3") {
-                let p = Path::new(&env_var).join("libndi.so");
-                if p.exists() {
-                    p.to_str().unwrap_or("libndi.so").to_string()
+            for path in &self.paths {
+                if path.is_dir() {
+                    dirs.push(path.clone());
                 } else {
-                    "libndi.so".to_string()
+                    res.push(path.clone());
+                }
+            }
+            if self.current_dir {
+                dirs.push(PathBuf::from("."));
+            }
+            if self.env_vars {
+                for var in RUNTIME_ENV_VARS {
+                    if let Some(dir) = env::var_os(var) {
+                        dirs.push(PathBuf::from(dir));
+                    }
                 }
-            } else {
-                "libndi.so".to_string()
             }
-        };
-
-        match Library::new(lib_path) {
-            Err(e) => Err(format!("Failed to load lib: {}", e)),
-            Ok(lib) => unsafe {
-                let symbol: std::io::Result<Symbol<fn() -> *const sdk::NDIlib_v3>> = lib.get(b"NDIlib_v3_load");
-                match symbol {
-                    Err(e) => Err(format!("Invalid lib: {}", e)),
-                    Ok(s) => {
-                        let instance = s();
-                        if instance.is_null() {
-                            Err("Library failed to initialise".to_string())
-                        } else {
-                            if inst.NDIlib_initialize.unwrap()() {
-                                Ok(NDIInstance {
-                                    handle: Arc::new(NDIHandle {
-                                        _handle: Some(lib),
-                                        instance: *instance,
-                                    }),
-                                })
-                            } else {
-                                Err("Init returned false".to_string())
-                            }
+            if self.system_dirs {
+                dirs.extend(SYSTEM_DIRS.iter().map(PathBuf::from));
+            }
+
+            for dir in dirs {
+                res.extend(LIBRARY_NAMES.iter().map(|name| dir.join(name)));
+            }
+            if self.system_default {
+                // A bare name lets the platform loader apply its own search paths
+                res.extend(LIBRARY_NAMES.iter().map(PathBuf::from));
+            }
+
+            res
+        }
+    }
+
+    type LoadFn = unsafe extern "C" fn() -> *const sdk::NDIlib_v3;
+
+    /// Open the first candidate which is a loadable library exposing `NDIlib_v3_load`
+    fn open_library(search: &LibrarySearch) -> Result<(Library, LoadFn), LoadError> {
+        let mut attempts = Vec::new();
+        for path in search.candidates() {
+            match Library::new(&path) {
+                Err(e) => attempts.push((path, e.to_string())),
+                Ok(lib) => {
+                    let symbol: std::io::Result<Symbol<LoadFn>> = unsafe { lib.get(b"NDIlib_v3_load") };
+                    match symbol {
+                        Err(e) => attempts.push((path, format!("Invalid lib: {}", e))),
+                        Ok(s) => {
+                            let load_fn = *s;
+                            return Ok((lib, load_fn));
                         }
                     }
                 }
-            },
+            }
         }
+        Err(LoadError::NotFound(attempts))
+    }
+
+    /// Initialise an instance of the SDK, using the default `LibrarySearch`
+    pub fn load() -> Result<NDIInstance, LoadError> {
+        load_with(&LibrarySearch::default())
+    }
+
+    /// Initialise an instance of the SDK, searching for the runtime library as described by `search`
+    ///
+    /// The runtime is shared by the whole process, so if it has already been loaded the existing runtime is returned
+    /// and `search` is ignored.
+    pub fn load_with(search: &LibrarySearch) -> Result<NDIInstance, LoadError> {
+        acquire(|| {
+            let (lib, load_fn) = open_library(search)?;
+            let instance = unsafe { load_fn().as_ref() };
+            match instance {
+                None => Err(LoadError::Invalid("NDIlib_v3_load returned null".to_string())),
+                Some(inst) => Ok((*inst, lib)),
+            }
+        })
     }
 }
@@ -5,17 +5,27 @@ use std::sync::Arc;
 #[allow(non_snake_case, non_camel_case_types, non_upper_case_globals, dead_code, clippy::all)]
 mod sdk;
 
+pub mod audio;
+pub mod convert;
 pub mod finder;
+pub mod format;
 mod instance;
+pub mod planes;
 pub mod receive;
 pub mod send;
+pub mod time;
 mod util;
 
-pub use crate::instance::load;
+pub use crate::instance::{load, LoadError};
+#[cfg(feature = "dynamic-link")]
+pub use crate::instance::{load_with, LibrarySearch};
 use crate::receive::{ReceiveBandwidth, ReceiveColorFormat, ReceiveCreateError, ReceiveInstance};
 use crate::send::{SendCreateError, SendInstance};
 
 /// A loaded SDK Instance
+///
+/// Every instance in the process shares the same runtime, which stays initialised until all instances, and every finder,
+/// sender and receiver created from them, have been dropped.
 pub struct NDIInstance {
     handle: Arc<NDIHandle>,
 }
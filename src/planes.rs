@@ -0,0 +1,158 @@
+use crate::format::FourCCType;
+
+/// One plane of a video frame
+#[derive(Debug, Clone, Copy)]
+pub struct Plane<'a> {
+    data: &'a [u8],
+    line_stride: usize,
+    row_bytes: usize,
+    height: usize,
+}
+impl<'a> Plane<'a> {
+    fn new(data: &'a [u8], line_stride: usize, row_bytes: usize, height: usize) -> Plane<'a> {
+        Plane {
+            data,
+            line_stride,
+            row_bytes,
+            height,
+        }
+    }
+
+    /// All of the data of the plane, including any padding at the end of each row
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+    /// The number of bytes from the start of one row to the next
+    pub fn line_stride(&self) -> usize {
+        self.line_stride
+    }
+    /// The number of bytes of pixel data in each row, excluding padding
+    pub fn row_bytes(&self) -> usize {
+        self.row_bytes
+    }
+    /// The number of rows
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    /// The pixel data of row `y`, excluding padding
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` is not less than `height()`
+    pub fn row(&self, y: usize) -> &'a [u8] {
+        assert!(
+            y < self.height,
+            "row {} out of range for plane of height {}",
+            y,
+            self.height
+        );
+        let start = y * self.line_stride;
+        &self.data[start..start + self.row_bytes]
+    }
+    /// The samples of row `y` for a 16 bit format, decoded from little endian
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` is not less than `height()`
+    pub fn row_u16(&self, y: usize) -> impl ExactSizeIterator<Item = u16> + 'a {
+        self.row(y).chunks_exact(2).map(|s| u16::from_le_bytes([s[0], s[1]]))
+    }
+    /// Iterate over the pixel data of each row, excluding padding
+    pub fn rows(&self) -> impl ExactSizeIterator<Item = &'a [u8]> + 'a {
+        let row_bytes = self.row_bytes;
+        self.data
+            .chunks(self.line_stride.max(1))
+            .take(self.height)
+            .map(move |row| &row[..row_bytes])
+    }
+}
+
+/// The planes of a video frame, laid out according to its FourCC
+#[derive(Debug, Clone, Copy)]
+pub enum VideoPlanes<'a> {
+    /// A single plane of interleaved pixels. Used by `UYVY`, `BGRA`, `BGRX`, `RGBA` and `RGBX`
+    Packed(Plane<'a>),
+    /// A plane of interleaved `UYVY` pixels, followed by a plane of alpha with one byte per pixel. Used by `UYVA`
+    PackedAlpha { packed: Plane<'a>, alpha: Plane<'a> },
+    /// A luma plane and two quarter size chroma planes. Used by `I420`, and `YV12` where the `v` plane comes first in
+    /// memory
+    Planar { y: Plane<'a>, u: Plane<'a>, v: Plane<'a> },
+    /// A luma plane, followed by a plane of interleaved `UV` chroma. Used by `NV12` where the chroma is half height, and
+    /// `P216` where it is full height and every sample is 16 bits
+    SemiPlanar { y: Plane<'a>, uv: Plane<'a> },
+    /// A `P216` frame, followed by a plane of 16 bit alpha. Used by `PA16`
+    SemiPlanarAlpha {
+        y: Plane<'a>,
+        uv: Plane<'a>,
+        alpha: Plane<'a>,
+    },
+}
+impl<'a> VideoPlanes<'a> {
+    /// Split `data` into the planes for a frame of the given format and size.
+    /// Returns None if the dimensions are invalid, the stride is too short to hold a line, or `data` is too short to hold
+    /// the frame
+    pub fn new(
+        data: &'a [u8],
+        four_cc: FourCCType,
+        width: i32,
+        height: i32,
+        line_stride: i32,
+    ) -> Option<VideoPlanes<'a>> {
+        let size = four_cc.buffer_size(width, height, line_stride).ok()?;
+        if data.len() < size {
+            return None;
+        }
+        let (width, height, stride) = (width as usize, height as usize, line_stride as usize);
+        let chroma_height = height.div_ceil(2);
+
+        Some(match four_cc {
+            FourCCType::UYVY => VideoPlanes::Packed(Plane::new(&data[..size], stride, width * 2, height)),
+            FourCCType::BGRA | FourCCType::BGRX | FourCCType::RGBA | FourCCType::RGBX => {
+                VideoPlanes::Packed(Plane::new(&data[..size], stride, width * 4, height))
+            }
+            FourCCType::UYVA => {
+                let (packed, alpha) = data[..size].split_at(stride * height);
+                VideoPlanes::PackedAlpha {
+                    packed: Plane::new(packed, stride, width * 2, height),
+                    alpha: Plane::new(alpha, width, width, height),
+                }
+            }
+            FourCCType::I420 | FourCCType::YV12 => {
+                let chroma_stride = stride / 2;
+                let (y, chroma) = data[..size].split_at(stride * height);
+                let (first, second) = chroma.split_at(chroma_stride * chroma_height);
+                let y = Plane::new(y, stride, width, height);
+                let first = Plane::new(first, chroma_stride, width / 2, chroma_height);
+                let second = Plane::new(second, chroma_stride, width / 2, chroma_height);
+                if four_cc == FourCCType::I420 {
+                    VideoPlanes::Planar { y, u: first, v: second }
+                } else {
+                    VideoPlanes::Planar { y, u: second, v: first }
+                }
+            }
+            FourCCType::NV12 => {
+                let (y, uv) = data[..size].split_at(stride * height);
+                VideoPlanes::SemiPlanar {
+                    y: Plane::new(y, stride, width, height),
+                    uv: Plane::new(uv, stride, width, chroma_height),
+                }
+            }
+            FourCCType::P216 => {
+                let (y, uv) = data[..size].split_at(stride * height);
+                VideoPlanes::SemiPlanar {
+                    y: Plane::new(y, stride, width * 2, height),
+                    uv: Plane::new(uv, stride, width * 2, height),
+                }
+            }
+            FourCCType::PA16 => {
+                let (y, rest) = data[..size].split_at(stride * height);
+                let (uv, alpha) = rest.split_at(stride * height);
+                VideoPlanes::SemiPlanarAlpha {
+                    y: Plane::new(y, stride, width * 2, height),
+                    uv: Plane::new(uv, stride, width * 2, height),
+                    alpha: Plane::new(alpha, stride, width * 2, height),
+                }
+            }
+        })
+    }
+}
@@ -1,20 +1,30 @@
+use crate::audio;
 use crate::finder::FindSource;
+use crate::format::FormatError;
+pub use crate::format::{FourCCType, FrameFormatType, VideoFormat};
+use crate::planes::VideoPlanes;
+use crate::send::{
+    create_ndi_send_audio_frame, create_ndi_send_video_frame, FrameBuildError, NDISendAudioFrame, NDISendVideoFrame,
+};
+use crate::time::{FrameRate, Timecode, Timestamp};
 use crate::util::to_ndi_source;
 use crate::{sdk, NDIHandle};
 use ptrplus::AsPtr;
-use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::ffi::CStr;
+use std::fmt;
 use std::ops::Deref;
+use std::os::raw::c_char;
 use std::ptr::{null, null_mut};
 use std::slice;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::sync::Arc;
 
-pub struct GuardedPointer<'a, T, T2> {
-    _guard: MutexGuard<'a, T>,
+/// The data of a received frame, along with the SDK's description of it. It borrows the frame, so cannot outlive it
+pub struct FrameData<'a, T, T2> {
+    frame: &'a T,
     value: &'a [T2],
 }
-impl<'a, T, T2> Deref for GuardedPointer<'a, T, T2> {
+impl<'a, T, T2> Deref for FrameData<'a, T, T2> {
     type Target = [T2];
 
     fn deref(&self) -> &[T2] {
@@ -22,202 +32,418 @@ impl<'a, T, T2> Deref for GuardedPointer<'a, T, T2> {
     }
 }
 
-pub type VideoFrameData<'a> = GuardedPointer<'a, sdk::NDIlib_video_frame_v2_t, u8>;
+pub type VideoFrameData<'a> = FrameData<'a, sdk::NDIlib_video_frame_v2_t, u8>;
+impl<'a> VideoFrameData<'a> {
+    /// Split the data into its planes, according to the FourCC of the frame
+    pub fn planes(&self) -> Option<VideoPlanes<'_>> {
+        let four_cc = FourCCType::try_from(self.frame.FourCC).ok()?;
+        VideoPlanes::new(
+            self.value,
+            four_cc,
+            self.frame.xres,
+            self.frame.yres,
+            self.frame.line_stride_in_bytes,
+        )
+    }
+}
+// The data of a frame is never written to once it has been captured, and the SDK allows frames to be freed from any thread
 unsafe impl Send for VideoFrame {}
 unsafe impl Sync for VideoFrame {}
 
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
-pub enum FrameFormatType {
-    Progressive = sdk::NDIlib_frame_format_type_progressive as isize,
-    Interlaced = sdk::NDIlib_frame_format_type_interleaved as isize,
-    Field0 = sdk::NDIlib_frame_format_type_field_0 as isize,
-    Field1 = sdk::NDIlib_frame_format_type_field_1 as isize,
-}
+pub struct VideoFrame {
+    instance: sdk::NDIlib_video_frame_v2_t,
+    /// The SDK requires every frame to be freed before its receiver is destroyed, so the receiver is kept alive
+    parent: Arc<ReceiveInstance>,
 
-impl TryFrom<u32> for FrameFormatType {
-    type Error = ();
+    pub width: i32,
+    pub height: i32,
 
-    fn try_from(v: u32) -> Result<Self, Self::Error> {
-        match v {
-            x if x == FrameFormatType::Progressive as u32 => Ok(FrameFormatType::Progressive),
-            x if x == FrameFormatType::Interlaced as u32 => Ok(FrameFormatType::Interlaced),
-            x if x == FrameFormatType::Field0 as u32 => Ok(FrameFormatType::Field0),
-            x if x == FrameFormatType::Field1 as u32 => Ok(FrameFormatType::Field1),
-            _ => Err(()),
-        }
+    pub frame_rate: FrameRate,
+    pub four_cc_type: FourCCType,
+    pub picture_aspect_ratio: f32,
+    pub frame_format_type: FrameFormatType,
+    pub timecode: Timecode,
+    /// The stride of the first plane. See `VideoFrameData::planes` for the layout of every plane
+    pub line_stride_in_bytes: i32,
+    pub metadata: Option<String>,
+    pub timestamp: Timestamp,
+}
+impl Drop for VideoFrame {
+    fn drop(&mut self) {
+        self.parent.free_video(&self.instance);
     }
 }
+impl VideoFrame {
+    /// Access the data of the frame, which is empty if the SDK did not provide any, or its layout is invalid
+    pub fn lock_data(&self) -> VideoFrameData<'_> {
+        let frame = &self.instance;
+        // Cover every plane of the frame, not just the first. A frame whose layout is invalid gets no data
+        let len = self
+            .four_cc_type
+            .buffer_size(frame.xres, frame.yres, frame.line_stride_in_bytes)
+            .unwrap_or(0);
+        FrameData {
+            frame,
+            value: unsafe { frame_slice(frame.p_data, len) },
+        }
+    }
 
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
-pub enum FourCCType {
-    UYVY = sdk::NDIlib_FourCC_type_UYVY as isize,
-    UYVA = sdk::NDIlib_FourCC_type_UYVA as isize,
-    // P216 = sdk::NDIlib_FourCC_type_P216 as isize,
-    // PA16 = sdk::NDIlib_FourCC_type_PA16 as isize,
-    YV12 = sdk::NDIlib_FourCC_type_YV12 as isize,
-    I420 = sdk::NDIlib_FourCC_type_I420 as isize,
-    NV12 = sdk::NDIlib_FourCC_type_NV12 as isize,
-    BGRA = sdk::NDIlib_FourCC_type_BGRA as isize,
-    BGRX = sdk::NDIlib_FourCC_type_BGRX as isize,
-    RGBA = sdk::NDIlib_FourCC_type_RGBA as isize,
-    RGBX = sdk::NDIlib_FourCC_type_RGBX as isize,
-}
-
-impl TryFrom<u32> for FourCCType {
-    type Error = ();
-
-    fn try_from(v: u32) -> Result<Self, Self::Error> {
-        match v {
-            x if x == FourCCType::UYVY as u32 => Ok(FourCCType::UYVY),
-            x if x == FourCCType::UYVA as u32 => Ok(FourCCType::UYVA),
-            x if x == FourCCType::YV12 as u32 => Ok(FourCCType::YV12),
-            x if x == FourCCType::I420 as u32 => Ok(FourCCType::I420),
-            x if x == FourCCType::NV12 as u32 => Ok(FourCCType::NV12),
-            x if x == FourCCType::BGRA as u32 => Ok(FourCCType::BGRA),
-            x if x == FourCCType::BGRX as u32 => Ok(FourCCType::BGRX),
-            x if x == FourCCType::RGBA as u32 => Ok(FourCCType::RGBA),
-            x if x == FourCCType::RGBX as u32 => Ok(FourCCType::RGBX),
-            _ => Err(()),
+    /// The format of the frame, to compare with that of other frames
+    pub fn format(&self) -> VideoFormat {
+        VideoFormat {
+            width: self.width,
+            height: self.height,
+            frame_rate: self.frame_rate,
+            four_cc_type: self.four_cc_type,
+            frame_format_type: self.frame_format_type,
         }
     }
-}
 
-pub struct VideoFrame {
-    id: usize,
-    instance: Arc<Mutex<sdk::NDIlib_video_frame_v2_t>>,
-    parent: Weak<ReceiveInstance>,
+    /// Copy the frame, so that it can be kept without holding on to the SDK's buffer
+    pub fn to_owned(&self) -> OwnedVideoFrame {
+        let data = self.lock_data();
+        OwnedVideoFrame {
+            width: self.width,
+            height: self.height,
+            frame_rate: self.frame_rate,
+            four_cc_type: self.four_cc_type,
+            picture_aspect_ratio: self.picture_aspect_ratio,
+            frame_format_type: self.frame_format_type,
+            timecode: self.timecode,
+            line_stride_in_bytes: self.line_stride_in_bytes,
+            metadata: self.metadata.clone(),
+            timestamp: self.timestamp,
+            data: data.to_vec(),
+        }
+    }
+}
 
+/// A copy of a received video frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedVideoFrame {
     pub width: i32,
     pub height: i32,
 
-    pub frame_rate_n: i32,
-    pub frame_rate_d: i32,
+    pub frame_rate: FrameRate,
     pub four_cc_type: FourCCType,
-    //    pub picture_aspect_ratio: f32,
+    pub picture_aspect_ratio: f32,
     pub frame_format_type: FrameFormatType,
-    pub timecode: i64,
-    //    pub p_data: *mut u8,
-    //    pub line_stride_in_bytes: ::std::os::raw::c_int,
-    //    pub p_metadata: *const ::std::os::raw::c_char,
-    pub timestamp: i64,
+    pub timecode: Timecode,
+    pub line_stride_in_bytes: i32,
+    pub metadata: Option<String>,
+    pub timestamp: Timestamp,
+    /// Every plane of the frame, laid out as for the received frame
+    pub data: Vec<u8>,
 }
-impl Drop for VideoFrame {
-    fn drop(&mut self) {
-        if let Some(parent) = self.parent.upgrade() {
-            parent.free_video(self.id);
+impl OwnedVideoFrame {
+    /// The format of the frame, to compare with that of other frames
+    pub fn format(&self) -> VideoFormat {
+        VideoFormat {
+            width: self.width,
+            height: self.height,
+            frame_rate: self.frame_rate,
+            four_cc_type: self.four_cc_type,
+            frame_format_type: self.frame_format_type,
+        }
+    }
+
+    /// Split the data into its planes, according to the FourCC of the frame
+    pub fn planes(&self) -> Option<VideoPlanes<'_>> {
+        VideoPlanes::new(
+            &self.data,
+            self.four_cc_type,
+            self.width,
+            self.height,
+            self.line_stride_in_bytes,
+        )
+    }
+
+    /// Build a frame to send this one on, with the same format, timing and metadata
+    pub fn into_send_frame(self) -> Result<NDISendVideoFrame, FrameBuildError> {
+        let builder = create_ndi_send_video_frame(self.width, self.height, self.frame_format_type)
+            .with_framerate(self.frame_rate)
+            .with_aspect_ratio(self.picture_aspect_ratio)
+            .with_timecode(self.timecode)
+            .with_timestamp(self.timestamp)
+            .with_data(self.data, self.line_stride_in_bytes, self.four_cc_type);
+        match self.metadata {
+            Some(metadata) => builder.with_metadata(metadata),
+            None => builder,
         }
+        .build()
     }
 }
-impl VideoFrame {
-    pub fn lock_data(&self) -> Option<VideoFrameData> {
-        if let Ok(locked) = self.instance.lock() {
-            unsafe {
-                let len = locked.line_stride_in_bytes * locked.yres;
-                let data = slice::from_raw_parts(locked.p_data, len as usize);
-                Some(GuardedPointer {
-                    _guard: locked,
-                    value: data,
-                })
-            }
-        } else {
-            None
+
+/// A change in the format of a stream of video, reported by `FormatTracker`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatChanged {
+    /// The previous format, or `None` for the first frame of the stream
+    pub old: Option<VideoFormat>,
+    pub new: VideoFormat,
+}
+
+/// Follows the format of received video, reporting only when it actually changes
+#[derive(Debug, Clone, Default)]
+pub struct FormatTracker {
+    current: Option<VideoFormat>,
+}
+impl FormatTracker {
+    pub fn new() -> FormatTracker {
+        FormatTracker::default()
+    }
+
+    /// The format of the last frame seen, if any
+    pub fn current(&self) -> Option<VideoFormat> {
+        self.current
+    }
+
+    /// Track the format of a received frame, giving the change if it differs from the previous frame
+    pub fn process(&mut self, frame: &VideoFrame) -> Option<FormatChanged> {
+        self.update(frame.format())
+    }
+
+    /// Track a format, giving the change if it differs from the previous one
+    pub fn update(&mut self, format: VideoFormat) -> Option<FormatChanged> {
+        if self.current == Some(format) {
+            return None;
         }
+        let old = self.current.replace(format);
+        Some(FormatChanged { old, new: format })
+    }
+
+    /// Forget the current format, so that the next frame is reported as a change, as after reconnecting to a source
+    pub fn reset(&mut self) {
+        self.current = None;
     }
 }
 
-pub type AudioFrameData<'a> = GuardedPointer<'a, sdk::NDIlib_audio_frame_v2_t, f32>;
+pub type AudioFrameData<'a> = FrameData<'a, sdk::NDIlib_audio_frame_v2_t, f32>;
+impl<'a> AudioFrameData<'a> {
+    /// The number of channels in the frame
+    pub fn channel_count(&self) -> usize {
+        self.frame.no_channels.max(0) as usize
+    }
+    /// The samples of channel `i`, excluding any padding before the next channel
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not less than `channel_count()`
+    pub fn channel(&self, i: usize) -> &[f32] {
+        let count = self.channel_count();
+        assert!(
+            i < count,
+            "channel {} out of range for frame with {} channels",
+            i,
+            count
+        );
+        let (stride, samples) = self.channel_layout();
+        &self.value[i * stride..i * stride + samples]
+    }
+    /// Iterate over the samples of each channel, excluding any padding between them
+    pub fn channels(&self) -> impl ExactSizeIterator<Item = &[f32]> + '_ {
+        (0..self.channel_count()).map(move |i| self.channel(i))
+    }
+    /// The stride between channels and the number of samples in each, both in samples.
+    /// Every channel is empty if the SDK did not provide any data
+    fn channel_layout(&self) -> (usize, usize) {
+        let stride = self.frame.channel_stride_in_bytes.max(0) as usize / 4;
+        let samples = (self.frame.no_samples.max(0) as usize).min(stride);
+        if self.value.len() < stride * self.channel_count() {
+            (0, 0)
+        } else {
+            (stride, samples)
+        }
+    }
+}
+// See VideoFrame
 unsafe impl Send for AudioFrame {}
 unsafe impl Sync for AudioFrame {}
 pub struct AudioFrame {
-    id: usize,
-    instance: Arc<Mutex<sdk::NDIlib_audio_frame_v2_t>>,
-    parent: Weak<ReceiveInstance>,
+    instance: sdk::NDIlib_audio_frame_v2_t,
+    /// The SDK requires every frame to be freed before its receiver is destroyed, so the receiver is kept alive
+    parent: Arc<ReceiveInstance>,
 
     pub sample_rate: i32,
     pub channel_count: i32,
     pub sample_count: i32,
-    pub timecode: i64,
+    pub timecode: Timecode,
     //    pub p_data: *mut f32,
-    //    pub channel_stride_in_bytes: ::std::os::raw::c_int,
-    //    pub p_metadata: *const ::std::os::raw::c_char,
-    pub timestamp: i64,
+    /// The number of bytes from the start of one channel to the next, which may include padding
+    pub channel_stride_in_bytes: i32,
+    pub metadata: Option<String>,
+    pub timestamp: Timestamp,
 }
 impl Drop for AudioFrame {
     fn drop(&mut self) {
-        if let Some(parent) = self.parent.upgrade() {
-            parent.free_audio(self.id);
-        }
+        self.parent.free_audio(&self.instance);
     }
 }
 impl AudioFrame {
-    pub fn lock_data(&self) -> Option<AudioFrameData> {
-        if let Ok(locked) = self.instance.lock() {
-            unsafe {
-                // Divide by four as this is a list of f32
-                let len = locked.channel_stride_in_bytes * locked.no_channels / 4; 
-                let data = slice::from_raw_parts(locked.p_data, len as usize);
-                Some(GuardedPointer {
-                    _guard: locked,
-                    value: data,
-                })
+    /// Access the data of the frame, which is empty if the SDK did not provide any
+    pub fn lock_data(&self) -> AudioFrameData<'_> {
+        let frame = &self.instance;
+        // Divide by four as this is a list of f32
+        let len = frame.channel_stride_in_bytes.max(0) as usize * frame.no_channels.max(0) as usize / 4;
+        FrameData {
+            frame,
+            value: unsafe { frame_slice(frame.p_data, len) },
+        }
+    }
+
+    /// Copy the frame, so that it can be kept without holding on to the SDK's buffer
+    pub fn to_owned(&self) -> OwnedAudioFrame {
+        let data = self.lock_data();
+        OwnedAudioFrame {
+            sample_rate: self.sample_rate,
+            channel_count: data.channel_count() as i32,
+            sample_count: data.channel_layout().1 as i32,
+            timecode: self.timecode,
+            metadata: self.metadata.clone(),
+            timestamp: self.timestamp,
+            data: data.channels().flatten().cloned().collect(),
+        }
+    }
+
+    /// Interleave the samples as 16 bit integers.
+    ///
+    /// `reference_level` is the number of dB above the +4 dBU floating point reference level at which the integer
+    /// samples reach full scale. The SDK recommends 20 dB when receiving, so that +4 dBU is a full scale signal
+    pub fn to_interleaved_i16(&self, reference_level: i32) -> Vec<i16> {
+        self.interleave(
+            |sdk| sdk.NDIlib_util_audio_to_interleaved_16s_v2,
+            |frame, p_data| sdk::NDIlib_audio_frame_interleaved_16s_t {
+                sample_rate: frame.sample_rate,
+                no_channels: frame.no_channels,
+                no_samples: frame.no_samples,
+                timecode: frame.timecode,
+                reference_level,
+                p_data,
+            },
+            |data, channels, samples, stride| {
+                audio::to_interleaved_i16(data, channels, samples, stride, reference_level)
+            },
+        )
+    }
+    /// Interleave the samples as 32 bit integers. See `to_interleaved_i16` for the meaning of `reference_level`
+    pub fn to_interleaved_i32(&self, reference_level: i32) -> Vec<i32> {
+        self.interleave(
+            |sdk| sdk.NDIlib_util_audio_to_interleaved_32s_v2,
+            |frame, p_data| sdk::NDIlib_audio_frame_interleaved_32s_t {
+                sample_rate: frame.sample_rate,
+                no_channels: frame.no_channels,
+                no_samples: frame.no_samples,
+                timecode: frame.timecode,
+                reference_level,
+                p_data,
+            },
+            |data, channels, samples, stride| {
+                audio::to_interleaved_i32(data, channels, samples, stride, reference_level)
+            },
+        )
+    }
+    /// Interleave the samples, keeping them as floating point
+    pub fn to_interleaved_f32(&self) -> Vec<f32> {
+        self.interleave(
+            |sdk| sdk.NDIlib_util_audio_to_interleaved_32f_v2,
+            |frame, p_data| sdk::NDIlib_audio_frame_interleaved_32f_t {
+                sample_rate: frame.sample_rate,
+                no_channels: frame.no_channels,
+                no_samples: frame.no_samples,
+                timecode: frame.timecode,
+                p_data,
+            },
+            audio::to_interleaved_f32,
+        )
+    }
+
+    /// Interleave the samples with the SDK if it provides `select`, or in Rust if not
+    fn interleave<T, D, S, H, F>(&self, select: S, header: H, fallback: F) -> Vec<T>
+    where
+        T: Copy + Default,
+        S: FnOnce(&sdk::NDIlib_v3) -> Option<unsafe extern "C" fn(*const sdk::NDIlib_audio_frame_v2_t, *mut D)>,
+        H: FnOnce(&sdk::NDIlib_audio_frame_v2_t, *mut T) -> D,
+        F: FnOnce(&[f32], usize, usize, usize) -> Vec<T>,
+    {
+        let data = self.lock_data();
+        let frame = data.frame;
+        let (stride, samples) = data.channel_layout();
+        let channels = data.channel_count();
+
+        match select(&self.parent.sdk_instance) {
+            // The SDK cannot be asked to read from a frame without any data
+            Some(f) if samples > 0 && samples == frame.no_samples as usize => {
+                let mut res = vec![T::default(); channels * samples];
+                let mut dst = header(frame, res.as_mut_ptr());
+                unsafe { f(frame, &mut dst) };
+                res
             }
-        } else {
-            None
+            _ => fallback(&data, channels, samples, stride),
         }
     }
 }
 
-struct ReceiveDataStore<T> {
-    data: Mutex<HashMap<usize, Arc<Mutex<T>>>>,
-    next_id: AtomicUsize,
+/// A copy of a received audio frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedAudioFrame {
+    pub sample_rate: i32,
+    pub channel_count: i32,
+    pub sample_count: i32,
+    pub timecode: Timecode,
+    pub metadata: Option<String>,
+    pub timestamp: Timestamp,
+    /// The samples of each channel in turn, without any padding between them
+    pub data: Vec<f32>,
 }
-impl<T> ReceiveDataStore<T> {
-    fn remove(&self, id: usize) -> Option<Arc<Mutex<T>>> {
-        if let Ok(mut data_store) = self.data.lock() {
-            if let Some(data) = data_store.remove(&id) {
-                Some(data)
-            } else {
-                None
-            }
-        } else {
-            None
-        }
+impl OwnedAudioFrame {
+    /// The samples of channel `i`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not less than `channel_count`
+    pub fn channel(&self, i: usize) -> &[f32] {
+        let count = self.channel_count.max(0) as usize;
+        assert!(
+            i < count,
+            "channel {} out of range for frame with {} channels",
+            i,
+            count
+        );
+        let samples = self.sample_count.max(0) as usize;
+        &self.data[i * samples..(i + 1) * samples]
+    }
+    /// Iterate over the samples of each channel
+    pub fn channels(&self) -> impl ExactSizeIterator<Item = &[f32]> + '_ {
+        (0..self.channel_count.max(0) as usize).map(move |i| self.channel(i))
     }
-    fn track(&self, data: T) -> Option<(usize, Arc<Mutex<T>>)> {
-        let video2 = Arc::new(Mutex::new(data));
 
-        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
-        if let Ok(mut frame_list) = self.data.lock() {
-            frame_list.insert(id, video2.clone());
-            Some((id, video2))
-        } else {
-            None
+    /// Build a frame to send this one on, with the same timing and metadata
+    pub fn into_send_frame(self) -> Result<NDISendAudioFrame, FrameBuildError> {
+        let builder = create_ndi_send_audio_frame(self.channel_count, self.sample_rate)
+            .with_timecode(self.timecode)
+            .with_timestamp(self.timestamp)
+            .with_data(self.data, self.sample_count);
+        match self.metadata {
+            Some(metadata) => builder.with_metadata(metadata),
+            None => builder,
         }
+        .build()
     }
 }
 
+// The SDK allows a receiver to capture from several threads at once, such as one capturing video and another audio, and
+// to connect and free frames while that happens
 unsafe impl Send for ReceiveInstance {}
-unsafe impl Sync for ReceiveInstance {} // TODO - is this true? what is safety of methods on instance like?
+unsafe impl Sync for ReceiveInstance {}
+/// A receiver, which is destroyed once it and every frame captured from it have been dropped.
+/// It can be shared between threads, to capture video and audio on separate threads as the SDK recommends
 pub struct ReceiveInstance {
     sdk_instance: Arc<NDIHandle>,
     instance: sdk::NDIlib_recv_instance_t,
-    video_frames: ReceiveDataStore<sdk::NDIlib_video_frame_v2_t>,
-    audio_frames: ReceiveDataStore<sdk::NDIlib_audio_frame_v2_t>,
 }
 impl Drop for ReceiveInstance {
     fn drop(&mut self) {
+        // Every captured frame holds a reference to the receiver, so they have all been freed by now
         unsafe {
-            if let Ok(frame_store) = self.video_frames.data.lock() {
-                for f in frame_store.values() {
-                    self.free_video_inner(f)
-                }
-            }
-            if let Ok(frame_store) = self.audio_frames.data.lock() {
-                for f in frame_store.values() {
-                    self.free_audio_inner(f)
-                }
-            }
-
             self.sdk_instance.NDIlib_recv_destroy.unwrap()(self.instance);
         }
     }
@@ -242,65 +468,67 @@ impl ReceiveInstance {
             }
         }
     }
-    fn free_video(&self, id: usize) {
-        if let Some(frame) = self.video_frames.remove(id) {
-            self.free_video_inner(&frame);
-        }
-    }
-    fn free_video_inner(&self, video: &Arc<Mutex<sdk::NDIlib_video_frame_v2_t>>) {
-        if let Ok(mut ndi_ref) = video.lock() {
-            unsafe {
-                self.sdk_instance.NDIlib_recv_free_video_v2.unwrap()(self.instance, &*ndi_ref);
-                ndi_ref.p_data = null_mut();
-            }
-        } else {
-            // TODO - ?
+    fn free_video(&self, video: &sdk::NDIlib_video_frame_v2_t) {
+        unsafe {
+            self.sdk_instance.NDIlib_recv_free_video_v2.unwrap()(self.instance, video);
         }
     }
-    fn free_audio(&self, id: usize) {
-        if let Some(frame) = self.audio_frames.remove(id) {
-            self.free_audio_inner(&frame);
+    fn free_audio(&self, audio: &sdk::NDIlib_audio_frame_v2_t) {
+        unsafe {
+            self.sdk_instance.NDIlib_recv_free_audio_v2.unwrap()(self.instance, audio);
         }
     }
-    fn free_audio_inner(&self, audio: &Arc<Mutex<sdk::NDIlib_audio_frame_v2_t>>) {
-        if let Ok(mut ndi_ref) = audio.lock() {
-            unsafe {
-                self.sdk_instance.NDIlib_recv_free_audio_v2.unwrap()(self.instance, &*ndi_ref);
-                ndi_ref.p_data = null_mut();
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReceiveCaptureError {
+    /// The SDK returned a type of frame which was not asked for, or which is not known
+    UnexpectedFrameType(u32),
+    /// A video frame has a FourCC or frame format type which is not known
+    Format(FormatError),
+    /// The SDK reported an error, which happens when the connection to the source is lost
+    ConnectionLost,
+}
+impl fmt::Display for ReceiveCaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReceiveCaptureError::UnexpectedFrameType(t) => {
+                write!(f, "NDIlib_recv_capture_v2 returned an unexpected frame type {}", t)
             }
-        } else {
-            // TODO - ?
+            ReceiveCaptureError::Format(e) => write!(f, "invalid captured video: {}", e),
+            ReceiveCaptureError::ConnectionLost => write!(f, "the connection to the source was lost"),
         }
     }
 }
-
-#[derive(Debug)]
-pub enum ReceiveCaptureError {
-    Failed, // TODO
-    Poisoned,
-    Invalid,
+impl std::error::Error for ReceiveCaptureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReceiveCaptureError::Format(e) => Some(e),
+            _ => None,
+        }
+    }
 }
-
-impl From<()> for ReceiveCaptureError {
-    fn from(_err: ()) -> ReceiveCaptureError {
-        ReceiveCaptureError::Invalid
+impl From<FormatError> for ReceiveCaptureError {
+    fn from(e: FormatError) -> Self {
+        ReceiveCaptureError::Format(e)
     }
 }
 
-#[derive(Debug)]
-pub enum ReceiveCaptureResultType {
-    None,
-    Video,
-    Audio,
-    Metadata,
-    Error,
-    StatusChange,
-}
 pub enum ReceiveCaptureResult {
     None,
     Video(VideoFrame),
     Audio(AudioFrame),
-    Metadata(u32),
+    Metadata(MetadataFrame),
+    /// The settings of the receiver changed, such as its tally or the source it is connected to, rather than a frame
+    /// being captured
+    StatusChange,
+}
+
+/// A metadata frame, which is copied and freed as soon as it is captured
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataFrame {
+    pub timecode: Timecode,
+    pub data: String,
 }
 
 pub trait ReceiveInstanceExt {
@@ -311,6 +539,10 @@ pub trait ReceiveInstanceExt {
         capture_metadata: bool,
         timeout: u32,
     ) -> Result<ReceiveCaptureResult, ReceiveCaptureError>;
+
+    /// Split into a handle for each type of frame, so that each can be captured on its own thread as the SDK
+    /// recommends. The handles share the receiver, which stays alive until they have all been dropped
+    fn split(&self) -> (VideoReceiver, AudioReceiver, MetadataReceiver);
 }
 
 impl ReceiveInstanceExt for Arc<ReceiveInstance> {
@@ -374,60 +606,192 @@ impl ReceiveInstanceExt for Arc<ReceiveInstance> {
         };
         match captured {
             sdk::NDIlib_frame_type_video => match video_data {
-                None => Err(ReceiveCaptureError::Failed),
-                Some(video_data) => match self.video_frames.track(video_data) {
-                    None => Err(ReceiveCaptureError::Poisoned),
-                    Some(v) => {
-                        let frame = VideoFrame {
-                            id: v.0,
-                            instance: v.1,
-                            parent: Arc::downgrade(self),
-
-                            width: video_data.xres,
-                            height: video_data.yres,
-
-                            frame_rate_d: video_data.frame_rate_D,
-                            frame_rate_n: video_data.frame_rate_N,
-
-                            timecode: video_data.timecode,
-                            four_cc_type: FourCCType::try_from(video_data.FourCC)?,
-                            frame_format_type: FrameFormatType::try_from(video_data.frame_format_type)?,
-                            timestamp: video_data.timestamp,
-                        };
-                        Ok(ReceiveCaptureResult::Video(frame))
-                    }
-                },
+                None => Err(ReceiveCaptureError::UnexpectedFrameType(captured)),
+                Some(video_data) => {
+                    let formats = FourCCType::try_from(video_data.FourCC)
+                        .and_then(|f| Ok((f, FrameFormatType::try_from(video_data.frame_format_type)?)));
+                    let (four_cc_type, frame_format_type) = match formats {
+                        Ok(formats) => formats,
+                        Err(e) => {
+                            self.free_video(&video_data);
+                            return Err(e.into());
+                        }
+                    };
+                    let frame = VideoFrame {
+                        instance: video_data,
+                        parent: self.clone(),
+
+                        width: video_data.xres,
+                        height: video_data.yres,
+
+                        frame_rate: FrameRate::new(video_data.frame_rate_N, video_data.frame_rate_D),
+                        picture_aspect_ratio: video_data.picture_aspect_ratio,
+
+                        timecode: Timecode::from(video_data.timecode),
+                        four_cc_type,
+                        frame_format_type,
+                        line_stride_in_bytes: video_data.line_stride_in_bytes,
+                        metadata: to_metadata(video_data.p_metadata),
+                        timestamp: Timestamp::from(video_data.timestamp),
+                    };
+                    Ok(ReceiveCaptureResult::Video(frame))
+                }
             },
             sdk::NDIlib_frame_type_audio => match audio_data {
-                None => Err(ReceiveCaptureError::Failed),
-                Some(audio_data) => match self.audio_frames.track(audio_data) {
-                    None => Err(ReceiveCaptureError::Poisoned),
-                    Some(v) => {
-                        let frame = AudioFrame {
-                            id: v.0,
-                            instance: v.1,
-                            parent: Arc::downgrade(self),
-
-                            sample_rate: audio_data.sample_rate,
-                            channel_count: audio_data.no_channels,
-                            sample_count: audio_data.no_samples,
-                            timecode: audio_data.timecode,
-                            timestamp: audio_data.timestamp,
-                        };
-                        Ok(ReceiveCaptureResult::Audio(frame))
+                None => Err(ReceiveCaptureError::UnexpectedFrameType(captured)),
+                Some(audio_data) => {
+                    let frame = AudioFrame {
+                        instance: audio_data,
+                        parent: self.clone(),
+
+                        sample_rate: audio_data.sample_rate,
+                        channel_count: audio_data.no_channels,
+                        sample_count: audio_data.no_samples,
+                        timecode: Timecode::from(audio_data.timecode),
+                        channel_stride_in_bytes: audio_data.channel_stride_in_bytes,
+                        metadata: to_metadata(audio_data.p_metadata),
+                        timestamp: Timestamp::from(audio_data.timestamp),
+                    };
+                    Ok(ReceiveCaptureResult::Audio(frame))
+                }
+            },
+            sdk::NDIlib_frame_type_metadata => match metadata {
+                None => Err(ReceiveCaptureError::UnexpectedFrameType(captured)),
+                Some(metadata) => {
+                    let frame = MetadataFrame {
+                        timecode: Timecode::from(metadata.timecode),
+                        data: to_metadata(metadata.p_data).unwrap_or_default(),
+                    };
+                    unsafe {
+                        self.sdk_instance.NDIlib_recv_free_metadata.unwrap()(self.instance, &metadata);
                     }
-                },
+                    Ok(ReceiveCaptureResult::Metadata(frame))
+                }
             },
             sdk::NDIlib_frame_type_none => Ok(ReceiveCaptureResult::None),
-            _ => Err(ReceiveCaptureError::Invalid),
+            sdk::NDIlib_frame_type_status_change => Ok(ReceiveCaptureResult::StatusChange),
+            sdk::NDIlib_frame_type_error => Err(ReceiveCaptureError::ConnectionLost),
+            _ => Err(ReceiveCaptureError::UnexpectedFrameType(captured)),
         }
     }
+
+    fn split(&self) -> (VideoReceiver, AudioReceiver, MetadataReceiver) {
+        (
+            VideoReceiver { receiver: self.clone() },
+            AudioReceiver { receiver: self.clone() },
+            MetadataReceiver { receiver: self.clone() },
+        )
+    }
 }
 
-#[derive(Debug)]
+/// What a split handle captured
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Captured<T> {
+    Frame(T),
+    /// Nothing arrived before the timeout
+    None,
+    /// The settings of the receiver changed, such as its tally or the source it is connected to
+    StatusChange,
+}
+impl<T> Captured<T> {
+    /// The frame, if one was captured
+    pub fn frame(self) -> Option<T> {
+        match self {
+            Captured::Frame(frame) => Some(frame),
+            _ => None,
+        }
+    }
+}
+
+/// Captures only video from a receiver. Created by `ReceiveInstanceExt::split`
+pub struct VideoReceiver {
+    receiver: Arc<ReceiveInstance>,
+}
+impl VideoReceiver {
+    pub fn receiver(&self) -> &Arc<ReceiveInstance> {
+        &self.receiver
+    }
+    /// Wait up to `timeout` milliseconds for a video frame
+    pub fn capture(&self, timeout: u32) -> Result<Captured<VideoFrame>, ReceiveCaptureError> {
+        match self.receiver.receive_capture(true, false, false, timeout)? {
+            ReceiveCaptureResult::Video(frame) => Ok(Captured::Frame(frame)),
+            ReceiveCaptureResult::StatusChange => Ok(Captured::StatusChange),
+            _ => Ok(Captured::None),
+        }
+    }
+}
+
+/// Captures only audio from a receiver. Created by `ReceiveInstanceExt::split`
+pub struct AudioReceiver {
+    receiver: Arc<ReceiveInstance>,
+}
+impl AudioReceiver {
+    pub fn receiver(&self) -> &Arc<ReceiveInstance> {
+        &self.receiver
+    }
+    /// Wait up to `timeout` milliseconds for an audio frame
+    pub fn capture(&self, timeout: u32) -> Result<Captured<AudioFrame>, ReceiveCaptureError> {
+        match self.receiver.receive_capture(false, true, false, timeout)? {
+            ReceiveCaptureResult::Audio(frame) => Ok(Captured::Frame(frame)),
+            ReceiveCaptureResult::StatusChange => Ok(Captured::StatusChange),
+            _ => Ok(Captured::None),
+        }
+    }
+}
+
+/// Captures only metadata from a receiver. Created by `ReceiveInstanceExt::split`
+pub struct MetadataReceiver {
+    receiver: Arc<ReceiveInstance>,
+}
+impl MetadataReceiver {
+    pub fn receiver(&self) -> &Arc<ReceiveInstance> {
+        &self.receiver
+    }
+    /// Wait up to `timeout` milliseconds for a metadata frame
+    pub fn capture(&self, timeout: u32) -> Result<Captured<MetadataFrame>, ReceiveCaptureError> {
+        match self.receiver.receive_capture(false, false, true, timeout)? {
+            ReceiveCaptureResult::Metadata(frame) => Ok(Captured::Frame(frame)),
+            ReceiveCaptureResult::StatusChange => Ok(Captured::StatusChange),
+            _ => Ok(Captured::None),
+        }
+    }
+}
+
+/// The data of a frame as a slice, which is empty if the SDK did not provide any
+///
+/// # Safety
+///
+/// `p_data` must be null, or point to `len` values which stay valid for the lifetime of the slice
+unsafe fn frame_slice<'a, T>(p_data: *mut T, len: usize) -> &'a [T] {
+    if p_data.is_null() {
+        &[]
+    } else {
+        slice::from_raw_parts(p_data, len)
+    }
+}
+
+/// Copy the metadata of a frame, as the SDK frees it along with the frame
+fn to_metadata(p_metadata: *const c_char) -> Option<String> {
+    if p_metadata.is_null() {
+        None
+    } else {
+        Some(unsafe { CStr::from_ptr(p_metadata) }.to_string_lossy().into_owned())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ReceiveCreateError {
+    /// The SDK failed to create the receiver
     Failed,
 }
+impl fmt::Display for ReceiveCreateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReceiveCreateError::Failed => write!(f, "NDIlib_recv_create_v3 failed to create a receiver"),
+        }
+    }
+}
+impl std::error::Error for ReceiveCreateError {}
 
 #[derive(Debug)]
 pub enum ReceiveBandwidth {
@@ -467,17 +831,6 @@ pub fn create_receive_instance(
     if instance.is_null() {
         Err(ReceiveCreateError::Failed)
     } else {
-        Ok(Arc::new(ReceiveInstance {
-            sdk_instance,
-            instance,
-            video_frames: ReceiveDataStore {
-                data: Mutex::new(HashMap::new()),
-                next_id: AtomicUsize::new(0),
-            },
-            audio_frames: ReceiveDataStore {
-                data: Mutex::new(HashMap::new()),
-                next_id: AtomicUsize::new(0),
-            },
-        }))
+        Ok(Arc::new(ReceiveInstance { sdk_instance, instance }))
     }
 }
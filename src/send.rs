@@ -1,165 +1,530 @@
-use crate::{sdk, NDIHandle};
+pub use crate::format::{FourCCType, FrameFormatType};
+use crate::time::{FrameRate, Timecode, Timestamp};
+use crate::{audio, sdk, NDIHandle};
 use std::ffi::CString;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::os::raw::c_char;
 use std::ptr::{null, null_mut};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
 
+// The SDK allows video and audio to be sent from different threads at the same time. Video is sent with the lock on
+// `in_flight_video` held, so that the frame the SDK is reading is always the one kept alive, while audio needs no lock
 unsafe impl Send for SendInstance {}
+unsafe impl Sync for SendInstance {}
+/// A sender. Audio and video may be sent from different threads at once by sharing it in an `Arc`
 pub struct SendInstance {
     sdk_instance: Arc<NDIHandle>,
     instance: sdk::NDIlib_send_instance_t,
-    in_flight_video: Option<NDISendVideoFrame>,
+    /// The last video frame sent asynchronously, which the SDK may still be reading
+    in_flight_video: Mutex<Option<NDISendVideoFrame>>,
 }
 impl Drop for SendInstance {
     fn drop(&mut self) {
+        let in_flight = self.in_flight_video.get_mut().unwrap_or_else(PoisonError::into_inner);
+        if in_flight.is_some() {
+            self.send_video_flush();
+        }
         unsafe {
-            if self.in_flight_video.is_some() {
-                self.send_video_flush();
-            }
-
             self.sdk_instance.NDIlib_send_destroy.unwrap()(self.instance);
         }
     }
 }
 impl SendInstance {
-    pub fn send_video(&mut self, frame: NDISendVideoFrame) {
+    fn lock_video(&self) -> MutexGuard<'_, Option<NDISendVideoFrame>> {
+        self.in_flight_video.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Send a frame, returning once the SDK has finished with it. The frame is dropped before this returns, so a pooled
+    /// buffer is immediately available for reuse
+    pub fn send_video(&self, frame: NDISendVideoFrame) {
+        let mut in_flight = self.lock_video();
         unsafe {
-            // TODO - is this going to be a race condition?
-            //            self.in_flight_video = Some(frame);
-            //            self.sdk_instance.NDIlib_send_send_video_v2.unwrap()(self.instance, &self.in_flight_video.as_ref().unwrap().instance);
             self.sdk_instance.NDIlib_send_send_video_v2.unwrap()(self.instance, &frame.instance);
-            self.in_flight_video = Some(frame);
         }
+        // A synchronous send also releases any frame sent asynchronously before it
+        *in_flight = None;
     }
-    pub fn send_video_async(&mut self, frame: NDISendVideoFrame) {
+    pub fn send_video_async(&self, frame: NDISendVideoFrame) {
+        let mut in_flight = self.lock_video();
         unsafe {
             self.sdk_instance.NDIlib_send_send_video_async_v2.unwrap()(self.instance, &frame.instance);
-            self.in_flight_video = Some(frame);
         }
+        // Replacing the previous frame drops it, now that the SDK has released it
+        *in_flight = Some(frame);
     }
-    pub fn send_video_flush(&mut self) {
+    pub fn send_video_flush(&self) {
+        let mut in_flight = self.lock_video();
         unsafe {
             self.sdk_instance.NDIlib_send_send_video_async_v2.unwrap()(self.instance, null());
-            self.in_flight_video = None;
         }
+        *in_flight = None;
+    }
+    /// Send video frames that borrow their data asynchronously, without copying it.
+    ///
+    /// The SDK keeps reading a frame sent asynchronously until the next frame is sent, or the sender is flushed. To
+    /// guarantee that, every frame sent through the `BorrowedVideoSender` must borrow data which outlives this call, and
+    /// the last frame is flushed before this returns (or unwinds).
+    pub fn send_video_async_borrowed<'buf, F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut BorrowedVideoSender<'_, 'buf>) -> R,
+    {
+        let mut sender = BorrowedVideoSender {
+            sender: self,
+            in_flight: None,
+        };
+        f(&mut sender)
+    }
+    /// Create a pool of buffers for frames of the given size and format, to be sent by this sender
+    pub fn create_video_frame_pool(
+        &self,
+        width: i32,
+        height: i32,
+        line_stride: i32,
+        format: FourCCType,
+    ) -> Result<VideoFramePool, FrameBuildError> {
+        let buffer_size = format.buffer_size(width, height, line_stride)?;
+        Ok(VideoFramePool {
+            inner: Arc::new(VideoFramePoolInner {
+                width,
+                height,
+                line_stride,
+                format,
+                buffer_size,
+                free: Mutex::new(Vec::new()),
+                allocated: AtomicUsize::new(0),
+            }),
+        })
     }
-    pub fn send_audio(&mut self, frame: NDISendAudioFrame) {
+    pub fn send_audio(&self, frame: NDISendAudioFrame) {
         unsafe {
             self.sdk_instance.NDIlib_send_send_audio_v2.unwrap()(self.instance, &frame.instance);
         }
     }
+
+    /// Send interleaved 16 bit audio, with a synthesized timecode.
+    ///
+    /// `reference_level` is the number of dB above the +4 dBU floating point reference level at which the integer
+    /// samples reach full scale. The SDK recommends 0 dB when sending
+    pub fn send_audio_interleaved_i16(
+        &self,
+        data: &[i16],
+        channel_count: i32,
+        sample_rate: i32,
+        reference_level: i32,
+    ) -> Result<(), FrameBuildError> {
+        let sample_count = interleaved_sample_count(data.len(), channel_count)?;
+        match self.sdk_instance.NDIlib_util_send_send_audio_interleaved_16s {
+            Some(f) => unsafe {
+                let frame = sdk::NDIlib_audio_frame_interleaved_16s_t {
+                    sample_rate,
+                    no_channels: channel_count,
+                    no_samples: sample_count,
+                    timecode: sdk::NDIlib_send_timecode_synthesize,
+                    reference_level,
+                    p_data: data.as_ptr() as *mut i16,
+                };
+                f(self.instance, &frame);
+            },
+            None => {
+                let planar = audio::from_interleaved_i16(data, channel_count as usize, reference_level);
+                self.send_audio_planar(planar, channel_count, sample_count, sample_rate);
+            }
+        }
+        Ok(())
+    }
+    /// Send interleaved 32 bit audio, with a synthesized timecode.
+    /// See `send_audio_interleaved_i16` for the meaning of `reference_level`
+    pub fn send_audio_interleaved_i32(
+        &self,
+        data: &[i32],
+        channel_count: i32,
+        sample_rate: i32,
+        reference_level: i32,
+    ) -> Result<(), FrameBuildError> {
+        let sample_count = interleaved_sample_count(data.len(), channel_count)?;
+        match self.sdk_instance.NDIlib_util_send_send_audio_interleaved_32s {
+            Some(f) => unsafe {
+                let frame = sdk::NDIlib_audio_frame_interleaved_32s_t {
+                    sample_rate,
+                    no_channels: channel_count,
+                    no_samples: sample_count,
+                    timecode: sdk::NDIlib_send_timecode_synthesize,
+                    reference_level,
+                    p_data: data.as_ptr() as *mut i32,
+                };
+                f(self.instance, &frame);
+            },
+            None => {
+                let planar = audio::from_interleaved_i32(data, channel_count as usize, reference_level);
+                self.send_audio_planar(planar, channel_count, sample_count, sample_rate);
+            }
+        }
+        Ok(())
+    }
+    /// Send interleaved floating point audio, with a synthesized timecode
+    pub fn send_audio_interleaved_f32(
+        &self,
+        data: &[f32],
+        channel_count: i32,
+        sample_rate: i32,
+    ) -> Result<(), FrameBuildError> {
+        let sample_count = interleaved_sample_count(data.len(), channel_count)?;
+        match self.sdk_instance.NDIlib_util_send_send_audio_interleaved_32f {
+            Some(f) => unsafe {
+                let frame = sdk::NDIlib_audio_frame_interleaved_32f_t {
+                    sample_rate,
+                    no_channels: channel_count,
+                    no_samples: sample_count,
+                    timecode: sdk::NDIlib_send_timecode_synthesize,
+                    p_data: data.as_ptr() as *mut f32,
+                };
+                f(self.instance, &frame);
+            },
+            None => {
+                let planar = audio::from_interleaved_f32(data, channel_count as usize);
+                self.send_audio_planar(planar, channel_count, sample_count, sample_rate);
+            }
+        }
+        Ok(())
+    }
+    /// Send planes of `sample_count` samples, for when the SDK cannot convert interleaved audio itself
+    fn send_audio_planar(&self, mut data: Vec<f32>, channel_count: i32, sample_count: i32, sample_rate: i32) {
+        let frame = sdk::NDIlib_audio_frame_v2_t {
+            sample_rate,
+            no_channels: channel_count,
+            no_samples: sample_count,
+            timecode: sdk::NDIlib_send_timecode_synthesize,
+            channel_stride_in_bytes: sample_count * 4,
+            p_data: data.as_mut_ptr(),
+            p_metadata: null(),
+            timestamp: 0,
+        };
+        unsafe {
+            self.sdk_instance.NDIlib_send_send_audio_v2.unwrap()(self.instance, &frame);
+        }
+    }
+}
+
+/// The number of samples in each channel of interleaved audio
+fn interleaved_sample_count(len: usize, channel_count: i32) -> Result<i32, FrameBuildError> {
+    if channel_count <= 0 {
+        return Err(FrameBuildError::InvalidChannelCount { channel_count });
+    }
+    let channels = channel_count as usize;
+    if !len.is_multiple_of(channels) {
+        return Err(FrameBuildError::InvalidDataLength {
+            expected: len.next_multiple_of(channels),
+            actual: len,
+        });
+    }
+    Ok((len / channels) as i32)
 }
 
-pub enum FrameFormatType {
-    Progressive = sdk::NDIlib_frame_format_type_progressive as isize,
-    Interleaved = sdk::NDIlib_frame_format_type_interleaved as isize,
-    Field0 = sdk::NDIlib_frame_format_type_field_0 as isize,
-    Field1 = sdk::NDIlib_frame_format_type_field_1 as isize,
+/// Sends borrowed video frames asynchronously. See `SendInstance::send_video_async_borrowed`.
+/// It borrows the sender mutably, so no other video can be sent while it exists
+pub struct BorrowedVideoSender<'s, 'buf> {
+    sender: &'s mut SendInstance,
+    in_flight: Option<BorrowedVideoFrame<'buf>>,
+}
+impl<'s, 'buf> Drop for BorrowedVideoSender<'s, 'buf> {
+    fn drop(&mut self) {
+        if self.in_flight.is_some() {
+            self.flush();
+        }
+    }
+}
+impl<'s, 'buf> BorrowedVideoSender<'s, 'buf> {
+    /// Queue a frame to be sent. The previous frame is released by the SDK before this returns
+    pub fn send(&mut self, frame: BorrowedVideoFrame<'buf>) {
+        let mut owned = self.sender.lock_video();
+        unsafe {
+            self.sender.sdk_instance.NDIlib_send_send_video_async_v2.unwrap()(self.sender.instance, &frame.instance);
+        }
+        self.in_flight = Some(frame);
+        *owned = None;
+    }
+    /// Wait for the SDK to release the last frame sent
+    pub fn flush(&mut self) {
+        self.sender.send_video_flush();
+        self.in_flight = None;
+    }
 }
 
-#[derive(Debug)]
-pub enum SendColorFormat {
-    Uyvy = sdk::NDIlib_FourCC_type_UYVY as isize,
-    Yv12 = sdk::NDIlib_FourCC_type_YV12 as isize,
-    Nv12 = sdk::NDIlib_FourCC_type_NV12 as isize,
-    I420 = sdk::NDIlib_FourCC_type_I420 as isize,
-    Bgra = sdk::NDIlib_FourCC_type_BGRA as isize,
-    Bgrx = sdk::NDIlib_FourCC_type_BGRX as isize,
-    Rgba = sdk::NDIlib_FourCC_type_RGBA as isize,
-    Rgbx = sdk::NDIlib_FourCC_type_RGBX as isize,
-    Uyva = sdk::NDIlib_FourCC_type_UYVA as isize,
+/// The data of a frame to send, which may belong to a `VideoFramePool`
+enum VideoFrameData {
+    Owned(Vec<u8>),
+    Pooled(PooledVideoBuffer),
+}
+impl VideoFrameData {
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        match self {
+            VideoFrameData::Owned(data) => data,
+            VideoFrameData::Pooled(data) => data,
+        }
+    }
 }
 
 pub struct NDISendVideoFrameBuilder {
     instance: sdk::NDIlib_video_frame_v2_t,
+    format: FourCCType,
     metadata: Option<String>,
-    data: Vec<u8>,
+    data: VideoFrameData,
+    /// A problem found while setting the data, reported by `build`
+    error: Option<FrameBuildError>,
 }
 impl NDISendVideoFrameBuilder {
-    pub fn with_framerate(mut self, num: i32, den: i32) -> Self {
-        self.instance.frame_rate_N = num;
-        self.instance.frame_rate_D = den;
+    /// Set the frame rate. This is required, as building a video frame without one fails with `InvalidFrameRate`
+    pub fn with_framerate(mut self, frame_rate: FrameRate) -> Self {
+        self.instance.frame_rate_N = frame_rate.numerator;
+        self.instance.frame_rate_D = frame_rate.denominator;
         self
     }
     pub fn with_aspect_ratio(mut self, aspect_ratio: f32) -> Self {
         self.instance.picture_aspect_ratio = aspect_ratio;
         self
     }
-    pub fn with_timecode(mut self, timecode: i64) -> Self {
-        self.instance.timecode = timecode;
+    pub fn with_timecode(mut self, timecode: Timecode) -> Self {
+        self.instance.timecode = timecode.into();
         self
     }
-    pub fn with_data(mut self, data: Vec<u8>, line_stride: i32, format: SendColorFormat) -> Self {
-        self.data = data;
+    pub fn with_data(mut self, data: Vec<u8>, line_stride: i32, format: FourCCType) -> Self {
+        self.data = VideoFrameData::Owned(data);
         self.instance.line_stride_in_bytes = line_stride;
-        self.instance.FourCC = format as u32;
+        self.format = format;
+        self.error = None;
+        self
+    }
+    /// Use a buffer from a `VideoFramePool`, which is returned to the pool once the SDK has finished with the frame.
+    /// The line stride and format are taken from the pool, and building fails if the frame is not the size of the pool's
+    /// frames, or the pool has been dropped
+    pub fn with_pooled_data(mut self, data: PooledVideoBuffer) -> Self {
+        self.error = match data.pool.upgrade() {
+            Some(pool) => {
+                self.instance.line_stride_in_bytes = pool.line_stride;
+                self.format = pool.format;
+                let (width, height) = (self.instance.xres, self.instance.yres);
+                if (width, height) != (pool.width, pool.height) {
+                    Some(FrameBuildError::MismatchedPoolDimensions {
+                        width,
+                        height,
+                        pool_width: pool.width,
+                        pool_height: pool.height,
+                    })
+                } else {
+                    None
+                }
+            }
+            None => Some(FrameBuildError::PoolDropped),
+        };
+        self.data = VideoFrameData::Pooled(data);
         self
     }
     pub fn with_metadata(mut self, metadata: String) -> Self {
         self.metadata = Some(metadata);
         self
     }
-    pub fn with_timestamp(mut self, timestamp: i64) -> Self {
-        self.instance.timestamp = timestamp;
+    pub fn with_timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.instance.timestamp = timestamp.into();
         self
     }
-    pub fn build(self) -> Result<NDISendVideoFrame, SendCreateError> {
-        // TODO - change return error type
+    /// Build the frame, checking that the frame rate is valid, and the data is large enough for the dimensions, stride
+    /// and format
+    pub fn build(self) -> Result<NDISendVideoFrame, FrameBuildError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
         let mut res = NDISendVideoFrame {
             instance: self.instance,
-            metadata: self.metadata,
+            metadata: to_metadata(self.metadata)?,
             data: self.data,
         };
+        res.instance.p_metadata = metadata_ptr(&res.metadata);
 
-        if let Some(metadata) = &res.metadata {
-            res.instance.p_metadata = CString::new(metadata.as_bytes())
-                .map_err(|_| SendCreateError::InvalidName)?
-                .as_ptr();
-        }
+        let data = res.data.as_mut_slice();
+        check_video_data(&mut res.instance, self.format, data.len())?;
+        res.instance.p_data = data.as_mut_ptr();
 
-        res.data
-            .resize((res.instance.line_stride_in_bytes * res.instance.yres) as usize, 0);
-        res.instance.p_data = res.data.as_mut_ptr();
+        Ok(res)
+    }
+    /// Build a frame which borrows `data`, rather than taking ownership of it
+    pub fn build_borrowed<'a>(
+        self,
+        data: &'a [u8],
+        line_stride: i32,
+        format: FourCCType,
+    ) -> Result<BorrowedVideoFrame<'a>, FrameBuildError> {
+        let mut res = BorrowedVideoFrame {
+            instance: self.instance,
+            metadata: to_metadata(self.metadata)?,
+            _data: PhantomData,
+        };
+        res.instance.p_metadata = metadata_ptr(&res.metadata);
+
+        res.instance.line_stride_in_bytes = line_stride;
+        check_video_data(&mut res.instance, format, data.len())?;
+        // The SDK does not write to the data when sending
+        res.instance.p_data = data.as_ptr() as *mut u8;
 
         Ok(res)
     }
 }
 
+fn to_metadata(metadata: Option<String>) -> Result<Option<CString>, FrameBuildError> {
+    metadata
+        .map(|m| CString::new(m).map_err(|_| FrameBuildError::InvalidMetadata))
+        .transpose()
+}
+
+/// The pointer to give the SDK for the metadata. The CString is kept in the frame, so stays valid until the frame is
+/// dropped, which is not until the SDK has finished with it
+fn metadata_ptr(metadata: &Option<CString>) -> *const c_char {
+    metadata.as_ref().map_or(null(), |m| m.as_ptr())
+}
+
+/// Check that `len` bytes is enough to hold the frame described by `instance` in `format`, and set its FourCC
+fn check_video_data(
+    instance: &mut sdk::NDIlib_video_frame_v2_t,
+    format: FourCCType,
+    len: usize,
+) -> Result<(), FrameBuildError> {
+    let frame_rate = FrameRate::new(instance.frame_rate_N, instance.frame_rate_D);
+    if !frame_rate.is_valid() {
+        return Err(FrameBuildError::InvalidFrameRate(frame_rate));
+    }
+    let expected = format.buffer_size(instance.xres, instance.yres, instance.line_stride_in_bytes)?;
+    if len < expected {
+        return Err(FrameBuildError::InvalidDataLength { expected, actual: len });
+    }
+    instance.FourCC = format as u32;
+    Ok(())
+}
+
 pub struct NDISendAudioFrameBuilder {
     instance: sdk::NDIlib_audio_frame_v2_t,
+    metadata: Option<String>,
     data: Vec<f32>,
+    channel_stride_in_bytes: Option<i32>,
+    /// A problem found while setting the data, reported by `build`
+    error: Option<FrameBuildError>,
 }
 
 impl NDISendAudioFrameBuilder {
-    pub fn with_timecode(mut self, timecode: i64) -> Self {
-        self.instance.timecode = timecode;
+    /// Create a builder from interleaved samples, splitting them into one plane per channel
+    pub fn from_interleaved(data: &[f32], channel_count: i32, sample_rate: i32) -> Self {
+        let builder = create_ndi_send_audio_frame(channel_count, sample_rate);
+        match interleaved_sample_count(data.len(), channel_count) {
+            Ok(sample_count) => {
+                builder.with_data(audio::from_interleaved_f32(data, channel_count as usize), sample_count)
+            }
+            Err(e) => NDISendAudioFrameBuilder {
+                error: Some(e),
+                ..builder
+            },
+        }
+    }
+
+    pub fn with_timecode(mut self, timecode: Timecode) -> Self {
+        self.instance.timecode = timecode.into();
         self
     }
+    /// Set the planar data, holding `sample_count` samples for each channel.
+    /// Unless `with_channel_stride` is used, each channel must directly follow the previous one
     pub fn with_data(mut self, data: Vec<f32>, sample_count: i32) -> Self {
         self.data = data;
         self.instance.no_samples = sample_count;
-        self.instance.channel_stride_in_bytes = (self.instance.no_samples)*4;
+        // Any problem with the data it replaces no longer applies
+        self.error = None;
         self
     }
-    pub fn with_timestamp(mut self, timestamp: i64) -> Self {
-        self.instance.timestamp = timestamp;
+    /// Set the data from one buffer per channel, replacing the channel count given when creating the builder.
+    /// Every channel must have the same number of samples
+    pub fn with_channels(mut self, channels: Vec<Vec<f32>>) -> Self {
+        let sample_count = channels.first().map_or(0, |c| c.len());
+        let error = channels
+            .iter()
+            .enumerate()
+            .find(|(_, c)| c.len() != sample_count)
+            .map(|(channel, c)| FrameBuildError::MismatchedChannelLength {
+                channel,
+                expected: sample_count,
+                actual: c.len(),
+            });
+        self.instance.no_channels = channels.len() as i32;
+        self.channel_stride_in_bytes = None;
+        NDISendAudioFrameBuilder {
+            error,
+            ..self.with_data(channels.concat(), sample_count as i32)
+        }
+    }
+    /// Set the number of bytes from the start of one channel to the next, for data with padding between the channels
+    pub fn with_channel_stride(mut self, channel_stride_in_bytes: i32) -> Self {
+        self.channel_stride_in_bytes = Some(channel_stride_in_bytes);
         self
     }
-    pub fn build(self) -> Result<NDISendAudioFrame, SendCreateError> {
-        // TODO - change return error type
+    pub fn with_metadata(mut self, metadata: String) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+    pub fn with_timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.instance.timestamp = timestamp.into();
+        self
+    }
+    pub fn build(mut self) -> Result<NDISendAudioFrame, FrameBuildError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        check_audio_data(&mut self.instance, self.channel_stride_in_bytes, self.data.len())?;
+
         let mut res = NDISendAudioFrame {
             instance: self.instance,
+            metadata: to_metadata(self.metadata)?,
             data: self.data,
         };
+        res.instance.p_metadata = metadata_ptr(&res.metadata);
         res.instance.p_data = res.data.as_mut_ptr();
         Ok(res)
     }
 }
+
+/// Check that `len` samples is exactly enough to hold the frame described by `instance`, and set its channel stride
+fn check_audio_data(
+    instance: &mut sdk::NDIlib_audio_frame_v2_t,
+    channel_stride_in_bytes: Option<i32>,
+    len: usize,
+) -> Result<(), FrameBuildError> {
+    let (channel_count, sample_count) = (instance.no_channels, instance.no_samples);
+    if channel_count <= 0 {
+        return Err(FrameBuildError::InvalidChannelCount { channel_count });
+    }
+    if sample_count < 0 {
+        return Err(FrameBuildError::InvalidSampleCount { sample_count });
+    }
+
+    let minimum = sample_count * 4;
+    let channel_stride = channel_stride_in_bytes.unwrap_or(minimum);
+    if channel_stride < minimum || channel_stride % 4 != 0 {
+        return Err(FrameBuildError::InvalidChannelStride {
+            channel_stride,
+            minimum,
+        });
+    }
+
+    let expected = channel_count as usize * channel_stride as usize / 4;
+    if len != expected {
+        return Err(FrameBuildError::InvalidDataLength { expected, actual: len });
+    }
+    instance.channel_stride_in_bytes = channel_stride;
+    Ok(())
+}
 pub fn create_ndi_send_video_frame(width: i32, height: i32, frame_type: FrameFormatType) -> NDISendVideoFrameBuilder {
     NDISendVideoFrameBuilder {
         instance: sdk::NDIlib_video_frame_v2_t {
             xres: width,
             yres: height,
-            FourCC: sdk::NDIlib_FourCC_type_BGRA,
+            FourCC: FourCCType::BGRA as u32,
             frame_rate_N: 0,
             frame_rate_D: 0,
             picture_aspect_ratio: 0.0,
@@ -170,8 +535,10 @@ pub fn create_ndi_send_video_frame(width: i32, height: i32, frame_type: FrameFor
             p_metadata: null(),
             timestamp: 0,
         },
+        format: FourCCType::BGRA,
         metadata: None,
-        data: vec![],
+        data: VideoFrameData::Owned(vec![]),
+        error: None,
     }
 }
 
@@ -187,26 +554,221 @@ pub fn create_ndi_send_audio_frame(channel_count: i32, sample_rate: i32) -> NDIS
             p_metadata: null(),
             timestamp: 0,
         },
+        metadata: None,
         data: vec![],
+        channel_stride_in_bytes: None,
+        error: None,
     }
 }
 
+// The pointers in a frame only point to data which it owns or borrows, so it can be built on a different thread to the
+// one which sends it
+unsafe impl Send for NDISendVideoFrame {}
 pub struct NDISendVideoFrame {
     instance: sdk::NDIlib_video_frame_v2_t,
-    metadata: Option<String>,
+    metadata: Option<CString>,
+    data: VideoFrameData,
+}
+
+unsafe impl<'a> Send for BorrowedVideoFrame<'a> {}
+/// A video frame which borrows its data. Created by `NDISendVideoFrameBuilder::build_borrowed`
+pub struct BorrowedVideoFrame<'a> {
+    instance: sdk::NDIlib_video_frame_v2_t,
+    metadata: Option<CString>,
+    _data: PhantomData<&'a [u8]>,
+}
+
+struct VideoFramePoolInner {
+    width: i32,
+    height: i32,
+    line_stride: i32,
+    format: FourCCType,
+    buffer_size: usize,
+    free: Mutex<Vec<Vec<u8>>>,
+    allocated: AtomicUsize,
+}
+
+/// A pool of buffers for video frames of a fixed size and format.
+///
+/// Buffers are handed out by `acquire`, and return to the pool once the frame they are used in has been released by the
+/// SDK, so sending a steady stream of frames does not allocate once the pool has warmed up.
+#[derive(Clone)]
+pub struct VideoFramePool {
+    inner: Arc<VideoFramePoolInner>,
+}
+impl VideoFramePool {
+    /// Take a buffer from the pool, allocating a new one if none are free. The contents are left from its previous use
+    pub fn acquire(&self) -> PooledVideoBuffer {
+        let free = self.inner.free.lock().unwrap_or_else(PoisonError::into_inner).pop();
+        let data = free.unwrap_or_else(|| {
+            self.inner.allocated.fetch_add(1, Ordering::Relaxed);
+            vec![0; self.inner.buffer_size]
+        });
+        PooledVideoBuffer {
+            data,
+            pool: Arc::downgrade(&self.inner),
+        }
+    }
+    pub fn width(&self) -> i32 {
+        self.inner.width
+    }
+    pub fn height(&self) -> i32 {
+        self.inner.height
+    }
+    pub fn line_stride(&self) -> i32 {
+        self.inner.line_stride
+    }
+    pub fn format(&self) -> FourCCType {
+        self.inner.format
+    }
+    /// The number of buffers waiting to be reused
+    pub fn available(&self) -> usize {
+        self.inner.free.lock().unwrap_or_else(PoisonError::into_inner).len()
+    }
+    /// The number of buffers the pool has allocated
+    pub fn allocated(&self) -> usize {
+        self.inner.allocated.load(Ordering::Relaxed)
+    }
+}
+
+/// A buffer from a `VideoFramePool`. It is returned to the pool when dropped
+pub struct PooledVideoBuffer {
     data: Vec<u8>,
+    pool: Weak<VideoFramePoolInner>,
+}
+impl Drop for PooledVideoBuffer {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.upgrade() {
+            let data = std::mem::take(&mut self.data);
+            pool.free.lock().unwrap_or_else(PoisonError::into_inner).push(data);
+        }
+    }
+}
+impl Deref for PooledVideoBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.data
+    }
+}
+impl DerefMut for PooledVideoBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
 }
 
+unsafe impl Send for NDISendAudioFrame {}
 pub struct NDISendAudioFrame {
     instance: sdk::NDIlib_audio_frame_v2_t,
+    metadata: Option<CString>,
     data: Vec<f32>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SendCreateError {
-    InvalidName,
+    /// The name contains a nul byte
+    InvalidName(String),
+    /// The SDK failed to create the sender
     Failed,
 }
+impl fmt::Display for SendCreateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendCreateError::InvalidName(name) => write!(f, "sender name {:?} contains a nul byte", name),
+            SendCreateError::Failed => write!(f, "NDIlib_send_create failed to create a sender"),
+        }
+    }
+}
+impl std::error::Error for SendCreateError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameBuildError {
+    /// The width or height is not positive, or the width is odd for a format with horizontally subsampled chroma
+    InvalidDimensions { width: i32, height: i32 },
+    /// The line stride is too small to hold a line of the frame
+    InvalidStride { line_stride: i32, minimum: i32 },
+    /// The data is too short for the dimensions, stride and format of the frame
+    InvalidDataLength { expected: usize, actual: usize },
+    /// The metadata contains a nul byte
+    InvalidMetadata,
+    /// The frame rate of a video frame was not set, or is not positive
+    InvalidFrameRate(FrameRate),
+    /// The number of audio channels is not positive
+    InvalidChannelCount { channel_count: i32 },
+    /// The number of audio samples is negative
+    InvalidSampleCount { sample_count: i32 },
+    /// The audio channel stride is too small to hold a channel, or is not a whole number of samples
+    InvalidChannelStride { channel_stride: i32, minimum: i32 },
+    /// An audio channel does not have the same number of samples as the first
+    MismatchedChannelLength {
+        channel: usize,
+        expected: usize,
+        actual: usize,
+    },
+    /// A pooled buffer was used for a frame of a different size to the frames of its pool
+    MismatchedPoolDimensions {
+        width: i32,
+        height: i32,
+        pool_width: i32,
+        pool_height: i32,
+    },
+    /// The pool a pooled buffer came from was dropped, so the layout of the buffer is unknown
+    PoolDropped,
+}
+impl fmt::Display for FrameBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameBuildError::InvalidDimensions { width, height } => {
+                write!(f, "invalid frame dimensions {}x{} for the format", width, height)
+            }
+            FrameBuildError::InvalidStride { line_stride, minimum } => write!(
+                f,
+                "line stride of {} bytes is less than the minimum of {}",
+                line_stride, minimum
+            ),
+            FrameBuildError::InvalidDataLength { expected, actual } => {
+                write!(f, "frame data has length {}, expected {}", actual, expected)
+            }
+            FrameBuildError::InvalidMetadata => write!(f, "frame metadata contains a nul byte"),
+            FrameBuildError::InvalidFrameRate(frame_rate) => write!(f, "invalid frame rate {}", frame_rate),
+            FrameBuildError::InvalidChannelCount { channel_count } => {
+                write!(f, "invalid audio channel count {}", channel_count)
+            }
+            FrameBuildError::InvalidSampleCount { sample_count } => {
+                write!(f, "invalid audio sample count {}", sample_count)
+            }
+            FrameBuildError::InvalidChannelStride {
+                channel_stride,
+                minimum,
+            } => write!(
+                f,
+                "audio channel stride of {} bytes is not a whole number of samples of at least {} bytes",
+                channel_stride, minimum
+            ),
+            FrameBuildError::MismatchedChannelLength {
+                channel,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "audio channel {} has {} samples, expected {}",
+                channel, actual, expected
+            ),
+            FrameBuildError::MismatchedPoolDimensions {
+                width,
+                height,
+                pool_width,
+                pool_height,
+            } => write!(
+                f,
+                "frame is {}x{}, but the buffer is from a pool of {}x{} frames",
+                width, height, pool_width, pool_height
+            ),
+            FrameBuildError::PoolDropped => write!(f, "the pool of the frame buffer was dropped"),
+        }
+    }
+}
+impl std::error::Error for FrameBuildError {}
 
 pub fn create_send_instance(
     sdk_instance: Arc<NDIHandle>,
@@ -214,7 +776,7 @@ pub fn create_send_instance(
     clock_video: bool,
     clock_audio: bool,
 ) -> Result<SendInstance, SendCreateError> {
-    let name2 = CString::new(name.as_bytes()).map_err(|_| SendCreateError::InvalidName)?;
+    let name2 = CString::new(name.as_bytes()).map_err(|_| SendCreateError::InvalidName(name.clone()))?;
 
     let props = sdk::NDIlib_send_create_t {
         p_ndi_name: name2.as_ptr(),
@@ -231,7 +793,7 @@ pub fn create_send_instance(
         Ok(SendInstance {
             sdk_instance,
             instance,
-            in_flight_video: None,
+            in_flight_video: Mutex::new(None),
         })
     }
 }
@@ -0,0 +1,401 @@
+//! Timecodes and timestamps of frames, both in the SDK's 100 ns units
+
+use crate::sdk;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The number of 100 ns units in a second
+const TICKS_PER_SECOND: i64 = 10_000_000;
+
+fn ticks_from_duration(duration: Duration) -> i64 {
+    duration.as_secs() as i64 * TICKS_PER_SECOND + duration.subsec_nanos() as i64 / 100
+}
+
+fn ticks_to_duration(ticks: i64) -> Option<Duration> {
+    if ticks < 0 {
+        return None;
+    }
+    Some(Duration::new(
+        (ticks / TICKS_PER_SECOND) as u64,
+        (ticks % TICKS_PER_SECOND) as u32 * 100,
+    ))
+}
+
+/// The timecode of a frame, which NDI treats as the time of day it was captured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Timecode {
+    /// Have the SDK fill in a timecode when the frame is sent
+    #[default]
+    Synthesize,
+    /// A timecode in 100 ns units
+    Value(i64),
+}
+impl From<i64> for Timecode {
+    fn from(v: i64) -> Self {
+        if v == sdk::NDIlib_send_timecode_synthesize {
+            Timecode::Synthesize
+        } else {
+            Timecode::Value(v)
+        }
+    }
+}
+impl From<Timecode> for i64 {
+    fn from(v: Timecode) -> i64 {
+        match v {
+            Timecode::Synthesize => sdk::NDIlib_send_timecode_synthesize,
+            Timecode::Value(v) => v,
+        }
+    }
+}
+impl From<Duration> for Timecode {
+    fn from(v: Duration) -> Self {
+        Timecode::Value(ticks_from_duration(v))
+    }
+}
+impl Timecode {
+    /// The timecode in 100 ns units, unless it is to be synthesized
+    pub fn value(self) -> Option<i64> {
+        match self {
+            Timecode::Synthesize => None,
+            Timecode::Value(v) => Some(v),
+        }
+    }
+
+    /// The timecode as a duration, unless it is to be synthesized or is negative
+    pub fn to_duration(self) -> Option<Duration> {
+        self.value().and_then(ticks_to_duration)
+    }
+
+    /// Move the timecode by `ticks` 100 ns units, leaving `Synthesize` alone. The result saturates, stopping short of the
+    /// value the SDK reads as `Synthesize`
+    pub fn offset(self, ticks: i64) -> Self {
+        match self {
+            Timecode::Synthesize => Timecode::Synthesize,
+            Timecode::Value(v) => {
+                Timecode::Value(v.saturating_add(ticks).min(sdk::NDIlib_send_timecode_synthesize - 1))
+            }
+        }
+    }
+
+    /// The SMPTE timecode of the frame at this time.
+    ///
+    /// `drop_frame` chooses drop frame labels, which are only possible where `FrameRate::supports_drop_frame` is true.
+    /// The hours wrap around at 24. Gives `None` for a timecode to be synthesized, a negative timecode, an invalid frame
+    /// rate, or drop frame at a rate which does not support it
+    pub fn to_smpte(self, frame_rate: FrameRate, drop_frame: bool) -> Option<SmpteTimecode> {
+        let rate = SmpteRate::new(frame_rate, drop_frame).ok()?;
+        let ticks = self.value().filter(|v| *v >= 0)? as i128;
+        let frames = ticks * frame_rate.numerator as i128 / (frame_rate.denominator as i128 * TICKS_PER_SECOND as i128);
+        Some(rate.label(frames as i64))
+    }
+
+    /// The timecode of the start of the frame labelled `smpte`, counting drop frame labels if `smpte.drop_frame` is set
+    pub fn from_smpte(smpte: &SmpteTimecode, frame_rate: FrameRate) -> Result<Timecode, TimecodeError> {
+        let rate = SmpteRate::new(frame_rate, smpte.drop_frame)?;
+        let frames = rate.frame(smpte)? as i128;
+        // Round up, so that converting back gives the same frame
+        let scale = frame_rate.numerator as i128;
+        let ticks = (frames * frame_rate.denominator as i128 * TICKS_PER_SECOND as i128 + scale - 1) / scale;
+        Ok(Timecode::Value(ticks as i64))
+    }
+}
+
+/// The frame rate of a video stream, as a fraction of frames per second
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrameRate {
+    pub numerator: i32,
+    pub denominator: i32,
+}
+impl FrameRate {
+    pub const FPS_23_976: FrameRate = FrameRate::new(24000, 1001);
+    pub const FPS_24: FrameRate = FrameRate::new(24, 1);
+    pub const FPS_25: FrameRate = FrameRate::new(25, 1);
+    pub const FPS_29_97: FrameRate = FrameRate::new(30000, 1001);
+    pub const FPS_30: FrameRate = FrameRate::new(30, 1);
+    pub const FPS_47_952: FrameRate = FrameRate::new(48000, 1001);
+    pub const FPS_48: FrameRate = FrameRate::new(48, 1);
+    pub const FPS_50: FrameRate = FrameRate::new(50, 1);
+    pub const FPS_59_94: FrameRate = FrameRate::new(60000, 1001);
+    pub const FPS_60: FrameRate = FrameRate::new(60, 1);
+    pub const FPS_100: FrameRate = FrameRate::new(100, 1);
+    pub const FPS_119_88: FrameRate = FrameRate::new(120000, 1001);
+    pub const FPS_120: FrameRate = FrameRate::new(120, 1);
+
+    pub const fn new(numerator: i32, denominator: i32) -> FrameRate {
+        FrameRate { numerator, denominator }
+    }
+
+    /// Whether both parts of the fraction are positive
+    pub fn is_valid(self) -> bool {
+        self.numerator > 0 && self.denominator > 0
+    }
+
+    /// Whether drop frame timecode can be used, which is for NTSC rates such as 29.97 and 59.94 fps whose nominal rate
+    /// is a multiple of 30
+    pub fn supports_drop_frame(self) -> bool {
+        SmpteRate::new(self, true).is_ok()
+    }
+
+    /// The frames per second as a floating point number
+    pub fn to_f64(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    /// The closest frame rate to `fps`. Rates within 0.05% of an NTSC rate, which is 1000/1001 of a multiple of 24 or
+    /// 30 fps such as 29.97, give its exact fraction, and anything else is rounded to a thousandth of a frame. Gives
+    /// `None` if `fps` is not finite, or rounds to a rate which is not valid
+    pub fn from_f64(fps: f64) -> Option<FrameRate> {
+        if !fps.is_finite() || fps <= 0.0 || fps > i32::MAX as f64 / 1001.0 {
+            return None;
+        }
+        let whole = fps.round();
+        let ntsc = (fps * 1.001).round();
+        let rate = if (fps - whole).abs() < 1e-6 {
+            FrameRate::new(whole as i32, 1)
+        } else if (ntsc % 24.0 == 0.0 || ntsc % 30.0 == 0.0) && ntsc > 0.0 && (fps - ntsc / 1.001).abs() / fps < 5e-4 {
+            FrameRate::new(ntsc as i32 * 1000, 1001)
+        } else {
+            FrameRate::new((fps * 1000.0).round() as i32, 1000)
+        };
+        Some(rate).filter(|r| r.is_valid())
+    }
+
+    /// The time taken by each frame, if the frame rate is valid
+    pub fn frame_duration(self) -> Option<Duration> {
+        if !self.is_valid() {
+            return None;
+        }
+        let nanos = self.denominator as u64 * 1_000_000_000 / self.numerator as u64;
+        Some(Duration::from_nanos(nanos))
+    }
+}
+impl From<FrameRate> for f64 {
+    fn from(v: FrameRate) -> f64 {
+        v.to_f64()
+    }
+}
+impl fmt::Display for FrameRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+/// The time at which a frame was sent, which the SDK may leave undefined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Timestamp {
+    #[default]
+    Undefined,
+    /// A time in 100 ns units since the Unix epoch
+    Value(i64),
+}
+impl From<i64> for Timestamp {
+    fn from(v: i64) -> Self {
+        if v == sdk::NDIlib_recv_timestamp_undefined {
+            Timestamp::Undefined
+        } else {
+            Timestamp::Value(v)
+        }
+    }
+}
+impl From<Timestamp> for i64 {
+    fn from(v: Timestamp) -> i64 {
+        match v {
+            Timestamp::Undefined => sdk::NDIlib_recv_timestamp_undefined,
+            Timestamp::Value(v) => v,
+        }
+    }
+}
+impl From<SystemTime> for Timestamp {
+    fn from(v: SystemTime) -> Self {
+        Timestamp::Value(match v.duration_since(UNIX_EPOCH) {
+            Ok(d) => ticks_from_duration(d),
+            Err(e) => -ticks_from_duration(e.duration()),
+        })
+    }
+}
+impl Timestamp {
+    /// The timestamp in 100 ns units, if it is defined
+    pub fn value(self) -> Option<i64> {
+        match self {
+            Timestamp::Undefined => None,
+            Timestamp::Value(v) => Some(v),
+        }
+    }
+
+    pub fn is_undefined(self) -> bool {
+        self == Timestamp::Undefined
+    }
+
+    /// The time since the Unix epoch, if the timestamp is defined and not before it
+    pub fn to_duration(self) -> Option<Duration> {
+        self.value().and_then(ticks_to_duration)
+    }
+
+    /// The timestamp as a system time, if it is defined
+    pub fn to_system_time(self) -> Option<SystemTime> {
+        let v = self.value()?;
+        match ticks_to_duration(v) {
+            Some(d) => UNIX_EPOCH.checked_add(d),
+            None => UNIX_EPOCH.checked_sub(ticks_to_duration(v.checked_neg()?)?),
+        }
+    }
+
+    /// Move the timestamp by `ticks` 100 ns units, leaving `Undefined` alone. The result saturates, stopping short of the
+    /// value the SDK reads as `Undefined`
+    pub fn offset(self, ticks: i64) -> Self {
+        match self {
+            Timestamp::Undefined => Timestamp::Undefined,
+            Timestamp::Value(v) => {
+                Timestamp::Value(v.saturating_add(ticks).min(sdk::NDIlib_recv_timestamp_undefined - 1))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimecodeError {
+    /// The string is not of the form `HH:MM:SS:FF`, or `HH:MM:SS;FF` for drop frame
+    InvalidFormat(String),
+    /// The frame rate is not positive
+    InvalidFrameRate(FrameRate),
+    /// A field is out of range for the frame rate, or the frame is one skipped by drop frame timecode
+    OutOfRange(SmpteTimecode),
+    /// Drop frame timecode was used for a frame rate which does not support it
+    DropFrameUnsupported(FrameRate),
+}
+impl fmt::Display for TimecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimecodeError::InvalidFormat(s) => write!(f, "{:?} is not a timecode of the form HH:MM:SS:FF", s),
+            TimecodeError::InvalidFrameRate(rate) => write!(f, "invalid frame rate {}", rate),
+            TimecodeError::OutOfRange(smpte) => write!(f, "timecode {} is out of range for the frame rate", smpte),
+            TimecodeError::DropFrameUnsupported(rate) => {
+                write!(f, "drop frame timecode is not supported at {} fps", rate)
+            }
+        }
+    }
+}
+impl std::error::Error for TimecodeError {}
+
+/// An SMPTE timecode label. Displays, and parses, as `HH:MM:SS:FF`, with a `;` before the frames for drop frame.
+/// Parsing also accepts `.` in place of `;`, and drop frame labels with every separator the same, such as `HH;MM;SS;FF`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SmpteTimecode {
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+    pub frames: u32,
+    pub drop_frame: bool,
+}
+impl fmt::Display for SmpteTimecode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{}{:02}",
+            self.hours,
+            self.minutes,
+            self.seconds,
+            if self.drop_frame { ';' } else { ':' },
+            self.frames
+        )
+    }
+}
+impl FromStr for SmpteTimecode {
+    type Err = TimecodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || TimecodeError::InvalidFormat(s.to_string());
+        let fields: Vec<&str> = s.split([':', ';', '.']).collect();
+        if fields.len() != 4
+            || fields
+                .iter()
+                .any(|f| f.is_empty() || !f.bytes().all(|b| b.is_ascii_digit()))
+        {
+            return Err(invalid());
+        }
+        let separators: Vec<char> = s.chars().filter(|c| matches!(c, ':' | ';' | '.')).collect();
+        // The separator before the frames is `;` or `.` for drop frame. The others are either all `:`, or all the same
+        // as the one before the frames, so a mix such as `01;02:03;04` is rejected
+        if separators[0] != separators[1] || (separators[0] != ':' && separators[0] != separators[2]) {
+            return Err(invalid());
+        }
+        let drop_frame = separators[2] != ':';
+        let number = |i: usize| fields[i].parse::<u32>().map_err(|_| invalid());
+        Ok(SmpteTimecode {
+            hours: number(0)?,
+            minutes: number(1)?,
+            seconds: number(2)?,
+            frames: number(3)?,
+            drop_frame,
+        })
+    }
+}
+
+/// The counting scheme of SMPTE timecode for a frame rate
+struct SmpteRate {
+    /// The frame rate rounded to a whole number
+    nominal: i64,
+    /// The number of frame labels skipped at the start of each minute, apart from every tenth, for drop frame
+    dropped: i64,
+}
+impl SmpteRate {
+    fn new(frame_rate: FrameRate, drop_frame: bool) -> Result<SmpteRate, TimecodeError> {
+        if !frame_rate.is_valid() {
+            return Err(TimecodeError::InvalidFrameRate(frame_rate));
+        }
+        let (n, d) = (frame_rate.numerator as i64, frame_rate.denominator as i64);
+        let nominal = ((n + d / 2) / d).max(1);
+        // 30000/1001 and 60000/1001 drop 2 and 4 labels a minute, to keep up with the wall clock
+        let dropped = if !drop_frame {
+            0
+        } else if n * 1001 == nominal * 1000 * d && nominal % 30 == 0 {
+            nominal / 15
+        } else {
+            return Err(TimecodeError::DropFrameUnsupported(frame_rate));
+        };
+        Ok(SmpteRate { nominal, dropped })
+    }
+
+    fn frames_per_minute(&self) -> i64 {
+        self.nominal * 60 - self.dropped
+    }
+
+    fn frames_per_ten_minutes(&self) -> i64 {
+        self.nominal * 600 - self.dropped * 9
+    }
+
+    fn label(&self, frame: i64) -> SmpteTimecode {
+        let mut frame = frame % (self.frames_per_ten_minutes() * 6 * 24);
+        if self.dropped > 0 {
+            let tens = frame / self.frames_per_ten_minutes();
+            let rest = frame % self.frames_per_ten_minutes();
+            frame += self.dropped * 9 * tens;
+            if rest > self.dropped {
+                frame += self.dropped * ((rest - self.dropped) / self.frames_per_minute());
+            }
+        }
+        SmpteTimecode {
+            hours: (frame / (self.nominal * 3600)) as u32,
+            minutes: (frame / (self.nominal * 60) % 60) as u32,
+            seconds: (frame / self.nominal % 60) as u32,
+            frames: (frame % self.nominal) as u32,
+            drop_frame: self.dropped > 0,
+        }
+    }
+
+    fn frame(&self, smpte: &SmpteTimecode) -> Result<i64, TimecodeError> {
+        let skipped = smpte.seconds == 0 && !smpte.minutes.is_multiple_of(10) && (smpte.frames as i64) < self.dropped;
+        if smpte.hours >= 24
+            || smpte.minutes >= 60
+            || smpte.seconds >= 60
+            || smpte.frames as i64 >= self.nominal
+            || skipped
+        {
+            return Err(TimecodeError::OutOfRange(*smpte));
+        }
+        let minutes = smpte.hours as i64 * 60 + smpte.minutes as i64;
+        let labels = (minutes * 60 + smpte.seconds as i64) * self.nominal + smpte.frames as i64;
+        Ok(labels - self.dropped * (minutes - minutes / 10))
+    }
+}
@@ -0,0 +1,278 @@
+#![cfg(feature = "dynamic-link")]
+
+mod common;
+
+use ndi_sdk::audio::matrix::ChannelMatrix;
+use ndi_sdk::audio::meter::AudioMeter;
+use ndi_sdk::audio::resample::Resampler;
+use ndi_sdk::receive::{AudioFrame, ReceiveCaptureResult, ReceiveInstance, ReceiveInstanceExt};
+use ndi_sdk::send::{create_ndi_send_audio_frame, FrameBuildError, NDISendAudioFrameBuilder};
+use ndi_sdk::time::{Timecode, Timestamp};
+use std::sync::Arc;
+
+fn capture_audio(receiver: &Arc<ReceiveInstance>) -> AudioFrame {
+    match receiver.receive_capture(false, true, false, 0).expect("capture") {
+        ReceiveCaptureResult::Audio(frame) => frame,
+        _ => panic!("expected an audio frame"),
+    }
+}
+
+#[test]
+fn interleaved_i16_round_trip() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("interleaved_i16".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "interleaved_i16");
+
+    // Three samples of stereo audio
+    let data = [0, 32767, -32767, 16384, 100, -1];
+    sender.send_audio_interleaved_i16(&data, 2, 48000, 0).unwrap();
+
+    let frame = capture_audio(&receiver);
+    assert_eq!(frame.channel_count, 2);
+    assert_eq!(frame.sample_count, 3);
+    assert_eq!(frame.sample_rate, 48000);
+
+    // Planar in the frame, with full scale at 1.0
+    let planar = frame.lock_data();
+    assert_eq!(planar.channel(0), [0.0, -1.0, 100.0 / 32767.0]);
+    assert_eq!(planar.channel(1), [1.0, 16384.0 / 32767.0, -1.0 / 32767.0]);
+
+    assert_eq!(frame.to_interleaved_i16(0), data);
+    assert_eq!(frame.to_interleaved_f32()[..2], [0.0, 1.0]);
+
+    // The stub was loaded without the SDK's utilities, so these were the library's own conversions
+    assert_eq!(common::util_calls(), 0);
+}
+
+#[test]
+fn interleaved_i32_round_trip() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("interleaved_i32".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "interleaved_i32");
+
+    let data = [i32::MAX, i32::MIN + 1, 0, 1 << 20, -(1 << 24), 123_456_789];
+    sender.send_audio_interleaved_i32(&data, 3, 44100, 0).unwrap();
+
+    let frame = capture_audio(&receiver);
+    assert_eq!(frame.channel_count, 3);
+    assert_eq!(frame.sample_count, 2);
+
+    // The samples pass through f32, so only keep 24 bits of precision
+    let back = frame.to_interleaved_i32(0);
+    for (a, b) in data.iter().zip(back.iter()) {
+        assert!((*a as i64 - *b as i64).abs() <= 256, "{} -> {}", a, b);
+    }
+}
+
+#[test]
+fn interleaved_f32_round_trip() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("interleaved_f32".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "interleaved_f32");
+
+    let data: Vec<f32> = (0..32).map(|i| i as f32 / 8.0 - 2.0).collect();
+    sender.send_audio_interleaved_f32(&data, 4, 48000).unwrap();
+    assert_eq!(
+        common::sender_stats("interleaved_f32").audio_sum,
+        data.iter().map(|s| *s as f64).sum::<f64>()
+    );
+
+    let frame = capture_audio(&receiver);
+    assert_eq!(frame.sample_count, 8);
+    assert_eq!(frame.to_interleaved_f32(), data);
+
+    // The first channel is every fourth sample
+    let planar = frame.lock_data();
+    let first: Vec<f32> = data.iter().step_by(4).cloned().collect();
+    assert_eq!(planar.channel(0), &first[..]);
+}
+
+#[test]
+fn channels_skip_padding() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("channels".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "channels");
+
+    // Three channels of five samples, each holding its own index
+    let data: Vec<f32> = (0..15).map(|i| (i % 3) as f32).collect();
+    sender.send_audio_interleaved_f32(&data, 3, 48000).unwrap();
+
+    let frame = capture_audio(&receiver);
+    // The stub pads each channel to 16 samples
+    assert_eq!(frame.channel_stride_in_bytes, 16 * 4);
+
+    let planar = frame.lock_data();
+    assert_eq!(planar.len(), 3 * 16);
+    assert_eq!(planar.channel_count(), 3);
+    let channels = planar.channels();
+    assert_eq!(channels.len(), 3);
+    for (i, channel) in channels.enumerate() {
+        assert_eq!(channel, [i as f32; 5]);
+        assert_eq!(channel, planar.channel(i));
+    }
+}
+
+#[test]
+#[should_panic(expected = "channel 2 out of range")]
+fn channel_out_of_range() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("channel_out_of_range".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "channel_out_of_range");
+
+    sender.send_audio_interleaved_f32(&[0.0; 4], 2, 48000).unwrap();
+    let frame = capture_audio(&receiver);
+    frame.lock_data().channel(2);
+}
+
+#[test]
+fn reference_level_and_clipping() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("reference_level".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "reference_level");
+
+    sender
+        .send_audio_interleaved_f32(&[1.0, -1.0, 10.0, 0.5], 1, 48000)
+        .unwrap();
+    let frame = capture_audio(&receiver);
+
+    assert_eq!(frame.to_interleaved_i16(0), [32767, -32767, 32767, 16384]);
+    // 20 dB of headroom puts 1.0 at a tenth of full scale
+    assert_eq!(frame.to_interleaved_i16(20), [3277, -3277, 32767, 1638]);
+
+    // And the reverse when sending
+    sender.send_audio_interleaved_i16(&[3277], 1, 48000, 20).unwrap();
+    let frame = capture_audio(&receiver);
+    assert!((frame.lock_data()[0] - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn invalid_interleaved_audio() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("invalid_interleaved".to_string(), false, false)
+        .expect("create sender");
+
+    assert_eq!(
+        sender.send_audio_interleaved_i16(&[0; 4], 0, 48000, 0),
+        Err(FrameBuildError::InvalidChannelCount { channel_count: 0 })
+    );
+    assert_eq!(
+        sender.send_audio_interleaved_f32(&[0.0; 5], 2, 48000),
+        Err(FrameBuildError::InvalidDataLength { expected: 6, actual: 5 })
+    );
+    assert_eq!(common::sender_stats("invalid_interleaved").audio_frames, 0);
+}
+
+#[test]
+fn builder_layouts_are_sent_correctly() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("builder_layouts".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "builder_layouts");
+
+    // Separate channels
+    let frame = create_ndi_send_audio_frame(1, 48000)
+        .with_channels(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]])
+        .build()
+        .unwrap();
+    sender.send_audio(frame);
+
+    // Interleaved
+    let frame = NDISendAudioFrameBuilder::from_interleaved(&[1.0, 4.0, 2.0, 5.0, 3.0, 6.0], 2, 48000)
+        .build()
+        .unwrap();
+    sender.send_audio(frame);
+
+    // Padded between the channels, which must not be sent
+    let frame = create_ndi_send_audio_frame(2, 48000)
+        .with_data(vec![1.0, 2.0, 3.0, 99.0, 4.0, 5.0, 6.0, 99.0], 3)
+        .with_channel_stride(4 * 4)
+        .build()
+        .unwrap();
+    sender.send_audio(frame);
+    assert_eq!(common::sender_stats("builder_layouts").audio_sum, 21.0);
+
+    for _ in 0..3 {
+        let frame = capture_audio(&receiver);
+        let data = frame.lock_data();
+        let channels: Vec<&[f32]> = data.channels().collect();
+        assert_eq!(channels, [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+    }
+}
+
+#[test]
+fn meter_reads_received_frames() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("meter".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "meter");
+
+    // The padding the stub adds between channels is NaN, so would show up if it was measured
+    let frame = create_ndi_send_audio_frame(2, 48000)
+        .with_channels(vec![vec![0.5, -0.25, 0.0], vec![0.0, 0.125, -0.75]])
+        .build()
+        .unwrap();
+    sender.send_audio(frame);
+
+    let mut meter = AudioMeter::new();
+    meter.process(&capture_audio(&receiver));
+    let snapshot = meter.snapshot();
+    assert_eq!(snapshot.sample_rate, 48000);
+    assert_eq!(snapshot.channels.len(), 2);
+    assert_eq!(snapshot.channels[0].peak, 0.5);
+    assert_eq!(snapshot.channels[1].peak, 0.75);
+    assert!(!snapshot.channels[1].true_peak.is_nan());
+}
+
+#[test]
+fn remix_and_resample_received_frames() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("remix".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "remix");
+
+    let frame = create_ndi_send_audio_frame(1, 44100)
+        .with_channels(vec![vec![0.5; 441]])
+        .with_timecode(Timecode::Value(10_000_000))
+        .with_timestamp(Timestamp::Value(20_000_000))
+        .build()
+        .unwrap();
+    sender.send_audio(frame);
+    let frame = capture_audio(&receiver);
+
+    let stereo = ChannelMatrix::remix(1, 2).process(&frame).unwrap();
+    assert_eq!(stereo.channels, [[0.5; 441], [0.5; 441]]);
+    assert_eq!(
+        (stereo.timecode, stereo.timestamp),
+        (Timecode::Value(10_000_000), Timestamp::Value(20_000_000))
+    );
+
+    // The first frame starts the stream, so its first output sample is at the same time
+    let mut resampler = Resampler::new(48000);
+    let resampled = resampler.process(&frame);
+    assert_eq!(resampled.sample_rate, 48000);
+    assert_eq!(
+        (resampled.timecode, resampled.timestamp),
+        (Timecode::Value(10_000_000), Timestamp::Value(20_000_000))
+    );
+    assert!(!resampled.channels[0].is_empty());
+
+    // And the result can be sent on
+    sender.send_audio(stereo.into_send_frame_builder().build().unwrap());
+    assert_eq!(common::sender_stats("remix").audio_sum, 0.5 * 441.0 * 2.0);
+}
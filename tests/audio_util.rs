@@ -0,0 +1,111 @@
+//! Interleaved audio, when the runtime provides the SDK's utilities to convert it. The stub only includes them if asked
+//! before it is loaded, so these are kept apart from the tests of the fallbacks in tests/audio.rs
+#![cfg(feature = "dynamic-link")]
+
+mod common;
+
+use ndi_sdk::receive::{AudioFrame, ReceiveCaptureResult, ReceiveInstance, ReceiveInstanceExt};
+use ndi_sdk::NDIInstance;
+use std::sync::Arc;
+
+fn load() -> NDIInstance {
+    common::enable_util();
+    common::load()
+}
+
+fn capture_audio(receiver: &Arc<ReceiveInstance>) -> AudioFrame {
+    match receiver.receive_capture(false, true, false, 0).expect("capture") {
+        ReceiveCaptureResult::Audio(frame) => frame,
+        _ => panic!("expected an audio frame"),
+    }
+}
+
+/// Run `f`, checking that it called the stub's utilities
+fn with_util<T, F: FnOnce() -> T>(f: F) -> T {
+    let before = common::util_calls();
+    let res = f();
+    assert!(common::util_calls() > before, "the SDK's utilities were not used");
+    res
+}
+
+#[test]
+fn interleaved_i16_round_trip() {
+    let instance = load();
+    let sender = instance
+        .create_send_instance("util_i16".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "util_i16");
+
+    let data = [0, 32767, -32767, 16384, 100, -1];
+    with_util(|| sender.send_audio_interleaved_i16(&data, 2, 48000, 0).unwrap());
+
+    let frame = capture_audio(&receiver);
+    assert_eq!(frame.channel_count, 2);
+    assert_eq!(frame.sample_count, 3);
+    let planar = frame.lock_data();
+    assert_eq!(planar.channel(0), [0.0, -1.0, 100.0 / 32767.0]);
+    assert_eq!(planar.channel(1), [1.0, 16384.0 / 32767.0, -1.0 / 32767.0]);
+
+    assert_eq!(with_util(|| frame.to_interleaved_i16(0)), data);
+}
+
+#[test]
+fn interleaved_i32_round_trip() {
+    let instance = load();
+    let sender = instance
+        .create_send_instance("util_i32".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "util_i32");
+
+    let data = [i32::MAX, i32::MIN + 1, 0, 1 << 20, -(1 << 24), 123_456_789];
+    with_util(|| sender.send_audio_interleaved_i32(&data, 3, 44100, 0).unwrap());
+
+    let frame = capture_audio(&receiver);
+    assert_eq!(frame.channel_count, 3);
+    assert_eq!(frame.sample_count, 2);
+    let back = with_util(|| frame.to_interleaved_i32(0));
+    for (a, b) in data.iter().zip(back.iter()) {
+        assert!((*a as i64 - *b as i64).abs() <= 256, "{} -> {}", a, b);
+    }
+}
+
+#[test]
+fn interleaved_f32_round_trip() {
+    let instance = load();
+    let sender = instance
+        .create_send_instance("util_f32".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "util_f32");
+
+    let data: Vec<f32> = (0..32).map(|i| i as f32 / 8.0 - 2.0).collect();
+    with_util(|| sender.send_audio_interleaved_f32(&data, 4, 48000).unwrap());
+    assert_eq!(
+        common::sender_stats("util_f32").audio_sum,
+        data.iter().map(|s| *s as f64).sum::<f64>()
+    );
+
+    // The received channels are padded, which the SDK has to skip
+    let frame = capture_audio(&receiver);
+    assert_eq!(frame.channel_stride_in_bytes, 16 * 4);
+    assert_eq!(with_util(|| frame.to_interleaved_f32()), data);
+}
+
+#[test]
+fn reference_level_and_clipping() {
+    let instance = load();
+    let sender = instance
+        .create_send_instance("util_reference_level".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "util_reference_level");
+
+    sender
+        .send_audio_interleaved_f32(&[1.0, -1.0, 10.0, 0.5], 1, 48000)
+        .unwrap();
+    let frame = capture_audio(&receiver);
+    assert_eq!(with_util(|| frame.to_interleaved_i16(0)), [32767, -32767, 32767, 16384]);
+    assert_eq!(with_util(|| frame.to_interleaved_i16(20)), [3277, -3277, 32767, 1638]);
+
+    with_util(|| sender.send_audio_interleaved_i16(&[3277], 1, 48000, 20).unwrap());
+    let frame = capture_audio(&receiver);
+    assert!((frame.lock_data()[0] - 1.0).abs() < 1e-4);
+}
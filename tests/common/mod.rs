@@ -0,0 +1,181 @@
+// Helpers shared by the integration tests
+
+#![allow(dead_code)]
+
+use libloading::{Library, Symbol};
+use ndi_sdk::finder::FindSource;
+use ndi_sdk::receive::{ReceiveBandwidth, ReceiveColorFormat, ReceiveInstance};
+use ndi_sdk::{LibrarySearch, NDIInstance};
+use std::env;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::{Arc, OnceLock};
+
+/// Build the stub runtime in tests/stub, returning the path to the shared library.
+/// Each test binary gets its own copy, so that they can be built in parallel
+pub fn stub_path() -> PathBuf {
+    static PATH: OnceLock<PathBuf> = OnceLock::new();
+    PATH.get_or_init(|| {
+        let source = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/stub/ndi_stub.rs");
+        let output = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!(
+            "{}ndi_stub_{}{}",
+            env::consts::DLL_PREFIX,
+            env!("CARGO_CRATE_NAME"),
+            env::consts::DLL_SUFFIX
+        ));
+
+        let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+        let status = Command::new(rustc)
+            .args(["--crate-type", "cdylib", "--edition", "2018", "-o"])
+            .arg(&output)
+            .arg(&source)
+            .status()
+            .expect("run rustc");
+        assert!(status.success(), "failed to build the stub runtime");
+
+        output
+    })
+    .clone()
+}
+
+/// Load the stub runtime through the public api
+pub fn load() -> NDIInstance {
+    ndi_sdk::load_with(&LibrarySearch::empty().with_path(stub_path())).expect("load stub runtime")
+}
+
+/// Have the stub provide the SDK's interleaved audio utilities. Only takes effect if the stub has not been loaded yet
+pub fn enable_util() {
+    let lib = Library::new(stub_path()).expect("open stub runtime");
+    unsafe {
+        let f: Symbol<unsafe extern "C" fn()> = lib.get(b"ndi_stub_enable_util").expect("stub symbol");
+        f()
+    }
+    // Keep the stub loaded, so that the setting is still there when it is loaded again
+    std::mem::forget(lib);
+}
+
+/// How many times the stub's interleaved audio utilities have been called
+pub fn util_calls() -> isize {
+    let lib = Library::new(stub_path()).expect("open stub runtime");
+    unsafe {
+        let f: Symbol<unsafe extern "C" fn() -> isize> = lib.get(b"ndi_stub_util_calls").expect("stub symbol");
+        f()
+    }
+}
+
+/// How many outstanding NDIlib_initialize calls the stub has seen
+pub fn stub_initialized() -> isize {
+    let lib = Library::new(stub_path()).expect("open stub runtime");
+    unsafe {
+        let f: Symbol<unsafe extern "C" fn() -> isize> = lib.get(b"ndi_stub_initialized").expect("stub symbol");
+        f()
+    }
+}
+
+/// How many frames captured from the stub have not been freed
+pub fn frames_outstanding() -> isize {
+    let lib = Library::new(stub_path()).expect("open stub runtime");
+    unsafe {
+        let f: Symbol<unsafe extern "C" fn() -> isize> = lib.get(b"ndi_stub_frames_outstanding").expect("stub symbol");
+        f()
+    }
+}
+
+/// How many receivers the stub has destroyed before freeing every frame captured from them
+pub fn receivers_destroyed_early() -> isize {
+    let lib = Library::new(stub_path()).expect("open stub runtime");
+    unsafe {
+        let f: Symbol<unsafe extern "C" fn() -> isize> = lib.get(b"ndi_stub_destroyed_early").expect("stub symbol");
+        f()
+    }
+}
+
+/// Queue metadata for receivers connected to the sender called `name`, as the stub has no way to send it
+pub fn queue_metadata(name: &str, data: &str, timecode: i64) {
+    let lib = Library::new(stub_path()).expect("open stub runtime");
+    let (name, data) = (CString::new(name).unwrap(), CString::new(data).unwrap());
+    unsafe {
+        let f: Symbol<unsafe extern "C" fn(*const c_char, *const c_char, i64)> =
+            lib.get(b"ndi_stub_queue_metadata").expect("stub symbol");
+        f(name.as_ptr(), data.as_ptr(), timecode)
+    }
+}
+
+/// Queue a status change for receivers connected to the sender called `name`, as the stub's settings never change
+pub fn queue_status_change(name: &str) {
+    let lib = Library::new(stub_path()).expect("open stub runtime");
+    let name = CString::new(name).unwrap();
+    unsafe {
+        let f: Symbol<unsafe extern "C" fn(*const c_char)> =
+            lib.get(b"ndi_stub_queue_status_change").expect("stub symbol");
+        f(name.as_ptr())
+    }
+}
+
+/// Create a receiver which captures whatever is sent by the sender called `name`
+pub fn loopback_receiver(ndi: &NDIInstance, name: &str) -> Arc<ReceiveInstance> {
+    let receiver = ndi
+        .create_receive_instance(ReceiveBandwidth::Highest, ReceiveColorFormat::Fastest)
+        .expect("create receiver");
+    let source = FindSource {
+        name: name.to_string(),
+        url: None,
+    };
+    assert!(receiver.connect(Some(&source)));
+    receiver
+}
+
+/// Mirror of `SenderStats` in tests/stub/ndi_stub.rs
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct RawSenderStats {
+    video_frames: u64,
+    video_in_flight: bool,
+    video_checksum: u64,
+    video_metadata: [c_char; 256],
+    audio_frames: u64,
+    audio_sum: f64,
+    audio_metadata: [c_char; 256],
+    video_overlaps: u64,
+}
+
+/// What the stub runtime has read from a sender
+#[derive(Debug)]
+pub struct SenderStats {
+    pub video_frames: u64,
+    pub video_in_flight: bool,
+    pub video_checksum: u64,
+    pub video_metadata: String,
+    pub audio_frames: u64,
+    pub audio_sum: f64,
+    pub audio_metadata: String,
+    pub video_overlaps: u64,
+}
+
+fn to_string(s: &[c_char]) -> String {
+    unsafe { CStr::from_ptr(s.as_ptr()).to_string_lossy().into_owned() }
+}
+
+/// What the stub has read from the sender called `name`
+pub fn sender_stats(name: &str) -> SenderStats {
+    let lib = Library::new(stub_path()).expect("open stub runtime");
+    let name = CString::new(name).unwrap();
+    unsafe {
+        let f: Symbol<unsafe extern "C" fn(*const c_char, *mut RawSenderStats) -> bool> =
+            lib.get(b"ndi_stub_sender_stats").expect("stub symbol");
+        let mut raw = std::mem::zeroed();
+        assert!(f(name.as_ptr(), &mut raw), "unknown sender");
+        SenderStats {
+            video_frames: raw.video_frames,
+            video_in_flight: raw.video_in_flight,
+            video_checksum: raw.video_checksum,
+            video_metadata: to_string(&raw.video_metadata),
+            audio_frames: raw.audio_frames,
+            audio_sum: raw.audio_sum,
+            audio_metadata: to_string(&raw.audio_metadata),
+            video_overlaps: raw.video_overlaps,
+        }
+    }
+}
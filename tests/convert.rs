@@ -0,0 +1,321 @@
+use ndi_sdk::convert::{convert, convert_into, ColorMatrix, ColorRange, ConvertError, ConvertOptions};
+use ndi_sdk::format::FourCCType;
+use ndi_sdk::send::FrameBuildError;
+
+const ALL: [FourCCType; 11] = [
+    FourCCType::UYVY,
+    FourCCType::UYVA,
+    FourCCType::P216,
+    FourCCType::PA16,
+    FourCCType::YV12,
+    FourCCType::I420,
+    FourCCType::NV12,
+    FourCCType::BGRA,
+    FourCCType::BGRX,
+    FourCCType::RGBA,
+    FourCCType::RGBX,
+];
+
+const OPTIONS: [ConvertOptions; 4] = [
+    ConvertOptions {
+        matrix: ColorMatrix::Bt601,
+        range: ColorRange::Limited,
+    },
+    ConvertOptions {
+        matrix: ColorMatrix::Bt601,
+        range: ColorRange::Full,
+    },
+    ConvertOptions {
+        matrix: ColorMatrix::Bt709,
+        range: ColorRange::Limited,
+    },
+    ConvertOptions {
+        matrix: ColorMatrix::Bt709,
+        range: ColorRange::Full,
+    },
+];
+
+/// An RGBA image where every 2x2 block has the same colour, so that no format loses detail to chroma subsampling
+fn rgba_blocks(width: usize, height: usize) -> Vec<u8> {
+    let mut data = vec![0; width * height * 4];
+    for y in 0..height {
+        for x in 0..width {
+            let block = (y / 2 * width / 2 + x / 2) as u32;
+            let seed = block.wrapping_mul(2_654_435_761);
+            let px = &mut data[(y * width + x) * 4..][..4];
+            px[0] = seed as u8;
+            px[1] = (seed >> 8) as u8;
+            px[2] = (seed >> 16) as u8;
+            px[3] = (seed >> 24) as u8;
+        }
+    }
+    data
+}
+
+fn rgba_to(format: FourCCType, width: i32, height: i32, options: &ConvertOptions) -> Vec<u8> {
+    let src = rgba_blocks(width as usize, height as usize);
+    convert(&src, FourCCType::RGBA, width, height, width * 4, format, options)
+        .unwrap()
+        .data
+}
+
+#[test]
+fn rgb_formats_round_trip_exactly() {
+    let (width, height) = (6, 4);
+    let options = ConvertOptions::default();
+    let src = rgba_blocks(width, height);
+
+    for &via in ALL.iter().filter(|f| f.is_rgb()) {
+        let mid = convert(
+            &src,
+            FourCCType::RGBA,
+            width as i32,
+            height as i32,
+            width as i32 * 4,
+            via,
+            &options,
+        )
+        .unwrap();
+        let back = convert(
+            &mid.data,
+            via,
+            mid.width,
+            mid.height,
+            mid.line_stride,
+            FourCCType::RGBA,
+            &options,
+        )
+        .unwrap();
+
+        for (i, (a, b)) in src.iter().zip(back.data.iter()).enumerate() {
+            let expected = if i % 4 == 3 && !via.has_alpha() { 255 } else { *a };
+            assert_eq!(*b, expected, "via {:?} at byte {}", via, i);
+        }
+    }
+}
+
+#[test]
+fn yuv_formats_round_trip_exactly() {
+    let (width, height) = (8, 6);
+
+    for options in OPTIONS.iter() {
+        for &from in ALL.iter().filter(|f| !f.is_rgb()) {
+            // Start from an 8 bit format so that every other format can hold the samples exactly
+            let start = if from.has_alpha() {
+                FourCCType::UYVA
+            } else {
+                FourCCType::UYVY
+            };
+            let reference = rgba_to(start, width, height, options);
+            let src = convert(
+                &reference,
+                start,
+                width,
+                height,
+                start.min_line_stride(width),
+                from,
+                options,
+            )
+            .unwrap();
+
+            for &to in ALL.iter().filter(|f| !f.is_rgb()) {
+                let converted = convert(&src.data, from, width, height, src.line_stride, to, options).unwrap();
+                let back = convert(
+                    &converted.data,
+                    to,
+                    width,
+                    height,
+                    converted.line_stride,
+                    start,
+                    options,
+                )
+                .unwrap();
+
+                if to.has_alpha() {
+                    assert_eq!(back.data, reference, "{:?} -> {:?}", from, to);
+                } else {
+                    // Alpha is lost, so only compare the packed plane
+                    let packed = (width * height * 2) as usize;
+                    assert_eq!(back.data[..packed], reference[..packed], "{:?} -> {:?}", from, to);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn rgb_round_trips_through_yuv() {
+    let (width, height) = (8, 4);
+    let src = rgba_blocks(width as usize, height as usize);
+
+    for options in OPTIONS.iter() {
+        for &via in ALL.iter().filter(|f| !f.is_rgb()) {
+            let mid = convert(&src, FourCCType::RGBA, width, height, width * 4, via, options).unwrap();
+            let back = convert(
+                &mid.data,
+                via,
+                width,
+                height,
+                mid.line_stride,
+                FourCCType::RGBA,
+                options,
+            )
+            .unwrap();
+
+            // 8 bit YUV cannot represent every RGB value, especially with limited range
+            let tolerance = if matches!(via, FourCCType::P216 | FourCCType::PA16) {
+                1
+            } else {
+                3
+            };
+            for (i, (a, b)) in src.iter().zip(back.data.iter()).enumerate() {
+                if i % 4 == 3 {
+                    let expected = if via.has_alpha() { *a } else { 255 };
+                    assert_eq!(*b, expected, "alpha via {:?}", via);
+                } else {
+                    // Colours outside of the YUV gamut are clipped, so allow a larger difference for saturated values
+                    let diff = (*a as i32 - *b as i32).abs();
+                    assert!(
+                        diff <= tolerance,
+                        "{:?} via {:?} at byte {}: {} -> {}",
+                        options,
+                        via,
+                        i,
+                        a,
+                        b
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn uyvy_of(rgb: [u8; 3], matrix: ColorMatrix) -> [u8; 4] {
+    let options = ConvertOptions {
+        matrix,
+        range: ColorRange::Limited,
+    };
+    let src = [rgb[0], rgb[1], rgb[2], 255, rgb[0], rgb[1], rgb[2], 255];
+    let out = convert(&src, FourCCType::RGBA, 2, 1, 8, FourCCType::UYVY, &options).unwrap();
+    [out.data[0], out.data[1], out.data[2], out.data[3]]
+}
+
+#[test]
+fn known_values() {
+    // U, Y, V, Y
+    assert_eq!(uyvy_of([255, 0, 0], ColorMatrix::Bt709), [102, 63, 240, 63]);
+    assert_eq!(uyvy_of([255, 0, 0], ColorMatrix::Bt601), [90, 81, 240, 81]);
+    assert_eq!(uyvy_of([255, 255, 255], ColorMatrix::Bt709), [128, 235, 128, 235]);
+    assert_eq!(uyvy_of([0, 0, 0], ColorMatrix::Bt601), [128, 16, 128, 16]);
+
+    let full = ConvertOptions {
+        matrix: ColorMatrix::Bt709,
+        range: ColorRange::Full,
+    };
+    let out = convert(&[255; 8], FourCCType::RGBA, 2, 1, 8, FourCCType::NV12, &full).unwrap();
+    assert_eq!(out.data, [255, 255, 128, 128]);
+}
+
+#[test]
+fn chroma_is_averaged() {
+    // Two pixels, one red and one blue, share a chroma sample
+    let src = [255, 0, 0, 255, 0, 0, 255, 255];
+    let out = convert(
+        &src,
+        FourCCType::RGBA,
+        2,
+        1,
+        8,
+        FourCCType::UYVY,
+        &ConvertOptions::default(),
+    )
+    .unwrap();
+    let red = uyvy_of([255, 0, 0], ColorMatrix::Bt709);
+    let blue = uyvy_of([0, 0, 255], ColorMatrix::Bt709);
+    assert_eq!(out.data[1], red[1]);
+    assert_eq!(out.data[3], blue[3]);
+    assert!((out.data[0] as i32 - (red[0] as i32 + blue[0] as i32) / 2).abs() <= 1);
+    assert!((out.data[2] as i32 - (red[2] as i32 + blue[2] as i32) / 2).abs() <= 1);
+}
+
+#[test]
+fn odd_height_and_padded_strides() {
+    let (width, height) = (4, 3);
+    let options = ConvertOptions::default();
+    let reference = rgba_to(FourCCType::UYVY, width, height, &options);
+
+    for &to in [FourCCType::I420, FourCCType::YV12, FourCCType::NV12, FourCCType::BGRX].iter() {
+        let stride = to.min_line_stride(width) + 16;
+        let mut dst = vec![0xaa; to.buffer_size(width, height, stride).unwrap()];
+        convert_into(
+            &reference,
+            FourCCType::UYVY,
+            width,
+            height,
+            width * 2,
+            &mut dst,
+            to,
+            stride,
+            &options,
+        )
+        .unwrap();
+
+        let back = convert(&dst, to, width, height, stride, FourCCType::UYVY, &options).unwrap();
+        // The odd last row has no partner for its chroma, so it keeps its own exactly
+        let last_row = (width * 2 * (height - 1)) as usize;
+        if !to.is_rgb() {
+            assert_eq!(back.data[last_row..], reference[last_row..], "{:?}", to);
+        }
+        // Padding is left alone
+        assert_eq!(dst[stride as usize - 1], 0xaa, "{:?}", to);
+    }
+}
+
+#[test]
+fn invalid_input() {
+    let options = ConvertOptions::default();
+    assert_eq!(
+        convert(&[0; 16], FourCCType::UYVY, 3, 2, 6, FourCCType::BGRA, &options).err(),
+        Some(ConvertError::Frame(FrameBuildError::InvalidDimensions {
+            width: 3,
+            height: 2
+        }))
+    );
+    assert_eq!(
+        convert(&[0; 16], FourCCType::UYVY, 4, 2, 6, FourCCType::BGRA, &options).err(),
+        Some(ConvertError::Frame(FrameBuildError::InvalidStride {
+            line_stride: 6,
+            minimum: 8
+        }))
+    );
+    assert_eq!(
+        convert(&[0; 15], FourCCType::UYVY, 4, 2, 8, FourCCType::BGRA, &options).err(),
+        Some(ConvertError::Frame(FrameBuildError::InvalidDataLength {
+            expected: 16,
+            actual: 15
+        }))
+    );
+    assert_eq!(
+        convert(&[0; 16], FourCCType::UYVY, 4, 2, 8, FourCCType::UYVY, &options).map(|f| f.data.len()),
+        Ok(16)
+    );
+
+    let mut dst = [0; 10];
+    assert_eq!(
+        convert_into(
+            &[0; 16],
+            FourCCType::UYVY,
+            4,
+            2,
+            8,
+            &mut dst,
+            FourCCType::UYVY,
+            8,
+            &options
+        ),
+        Err(ConvertError::Frame(FrameBuildError::InvalidDataLength {
+            expected: 16,
+            actual: 10
+        }))
+    );
+}
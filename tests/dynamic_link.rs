@@ -0,0 +1,33 @@
+#![cfg(feature = "dynamic-link")]
+
+mod common;
+
+use ndi_sdk::LibrarySearch;
+use std::path::PathBuf;
+
+#[test]
+fn load_stub_runtime() {
+    let instance = common::load();
+    assert!(common::stub_initialized() > 0);
+
+    let finder = instance.create_find_instance(true).expect("create finder");
+    assert!(finder.wait_for_sources(0));
+    let sources = finder.get_current_sources();
+    assert_eq!(sources.len(), 1);
+    assert_eq!(sources[0].name, "STUB (Source 1)");
+    assert!(sources[0].url.is_none());
+}
+
+#[test]
+fn load_falls_through_to_later_paths() {
+    let stub = common::stub_path();
+    let search = LibrarySearch::empty()
+        .with_path("/nonexistent/libndi.so")
+        .with_path(stub.clone());
+    assert_eq!(
+        search.candidates()[..2],
+        [PathBuf::from("/nonexistent/libndi.so"), stub]
+    );
+
+    ndi_sdk::load_with(&search).expect("load stub runtime");
+}
@@ -0,0 +1,44 @@
+//! Loads which are expected to fail. Once a runtime has been loaded, `load_with` returns it and ignores its search, so
+//! these are kept apart from every test which loads the stub
+#![cfg(feature = "dynamic-link")]
+
+use ndi_sdk::{LibrarySearch, LoadError};
+use std::path::PathBuf;
+
+#[test]
+fn load_reports_every_path_tried() {
+    let search = LibrarySearch::empty()
+        .with_path("/nonexistent/libndi.so.5")
+        .with_path("/nonexistent/libndi.so.3");
+
+    match ndi_sdk::load_with(&search) {
+        Err(LoadError::NotFound(attempts)) => {
+            let paths: Vec<_> = attempts.into_iter().map(|(path, _)| path).collect();
+            assert_eq!(
+                paths,
+                vec![
+                    PathBuf::from("/nonexistent/libndi.so.5"),
+                    PathBuf::from("/nonexistent/libndi.so.3")
+                ]
+            );
+        }
+        Err(e) => panic!("unexpected error: {:?}", e),
+        Ok(_) => panic!("expected load to fail"),
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn load_rejects_library_without_entry_point() {
+    // The C library is always present, but does not expose NDIlib_v3_load
+    let search = LibrarySearch::empty().with_path("libc.so.6");
+
+    match ndi_sdk::load_with(&search) {
+        Err(LoadError::NotFound(attempts)) => {
+            assert_eq!(attempts.len(), 1);
+            assert!(attempts[0].1.starts_with("Invalid lib"));
+        }
+        Err(e) => panic!("unexpected error: {:?}", e),
+        Ok(_) => panic!("expected load to fail"),
+    }
+}
@@ -0,0 +1,84 @@
+#![cfg(feature = "dynamic-link")]
+
+mod common;
+
+use ndi_sdk::convert::ConvertError;
+use ndi_sdk::format::FormatError;
+use ndi_sdk::receive::ReceiveCaptureError;
+use ndi_sdk::send::{create_ndi_send_audio_frame, FrameBuildError, SendCreateError};
+use ndi_sdk::LoadError;
+use std::error::Error;
+use std::path::PathBuf;
+
+#[test]
+fn errors_describe_what_went_wrong() {
+    assert_eq!(
+        FrameBuildError::InvalidDataLength { expected: 8, actual: 6 }.to_string(),
+        "frame data has length 6, expected 8"
+    );
+    assert_eq!(
+        FrameBuildError::MismatchedChannelLength {
+            channel: 1,
+            expected: 3,
+            actual: 2
+        }
+        .to_string(),
+        "audio channel 1 has 2 samples, expected 3"
+    );
+    assert_eq!(
+        ConvertError::from(FrameBuildError::InvalidStride {
+            line_stride: 4,
+            minimum: 8
+        })
+        .to_string(),
+        "line stride of 4 bytes is less than the minimum of 8"
+    );
+
+    let e = ReceiveCaptureError::from(FormatError::UnknownFourCC(0x1234_5678));
+    assert_eq!(e.to_string(), "invalid captured video: unknown FourCC 0x12345678");
+    assert_eq!(
+        e.source().and_then(|e| e.downcast_ref::<FormatError>()),
+        Some(&FormatError::UnknownFourCC(0x1234_5678))
+    );
+}
+
+#[test]
+fn sender_name_is_reported() {
+    let instance = common::load();
+    match instance.create_send_instance("bad\0name".to_string(), false, false) {
+        Err(e) => {
+            assert_eq!(e, SendCreateError::InvalidName("bad\0name".to_string()));
+            assert_eq!(e.to_string(), "sender name \"bad\\0name\" contains a nul byte");
+        }
+        Ok(_) => panic!("expected an invalid name"),
+    }
+}
+
+#[test]
+fn load_error_lists_every_path() {
+    let e = LoadError::NotFound(vec![
+        (PathBuf::from("/nonexistent/first.so"), "not found".to_string()),
+        (PathBuf::from("/nonexistent/second.so"), "not found".to_string()),
+    ]);
+    assert_eq!(
+        e.to_string(),
+        "failed to find the NDI runtime: /nonexistent/first.so: not found; /nonexistent/second.so: not found"
+    );
+}
+
+#[test]
+fn errors_compose_with_question_mark() {
+    fn send() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let instance = common::load();
+        let sender = instance.create_send_instance("compose".to_string(), false, false)?;
+        let frame = create_ndi_send_audio_frame(0, 48000).build()?;
+        sender.send_audio(frame);
+        Ok(())
+    }
+
+    let e = send().unwrap_err();
+    assert_eq!(
+        e.downcast_ref::<FrameBuildError>(),
+        Some(&FrameBuildError::InvalidChannelCount { channel_count: 0 })
+    );
+}
@@ -0,0 +1,126 @@
+use ndi_sdk::format::{ChromaSubsampling, FormatError, FourCCType, FrameFormatType, VideoFormat};
+use ndi_sdk::receive::{FormatChanged, FormatTracker};
+use ndi_sdk::time::FrameRate;
+use std::convert::TryFrom;
+
+const ALL: [FourCCType; 11] = [
+    FourCCType::UYVY,
+    FourCCType::UYVA,
+    FourCCType::P216,
+    FourCCType::PA16,
+    FourCCType::YV12,
+    FourCCType::I420,
+    FourCCType::NV12,
+    FourCCType::BGRA,
+    FourCCType::BGRX,
+    FourCCType::RGBA,
+    FourCCType::RGBX,
+];
+
+#[test]
+fn four_cc_round_trips() {
+    for &format in ALL.iter() {
+        let name = format.to_string();
+        assert_eq!(name, format!("{:?}", format));
+        assert_eq!(name.parse::<FourCCType>(), Ok(format));
+        assert_eq!(FourCCType::try_from(u32::from(format)), Ok(format));
+    }
+    // The characters are packed little endian, as by the SDK's NDI_LIB_FOURCC
+    assert_eq!(u32::from(FourCCType::UYVY), u32::from_le_bytes(*b"UYVY"));
+}
+
+#[test]
+fn unknown_four_cc() {
+    assert_eq!(
+        "uyvy".parse::<FourCCType>(),
+        Err(FormatError::InvalidFourCC("uyvy".to_string()))
+    );
+    assert_eq!(
+        "UYVY2".parse::<FourCCType>(),
+        Err(FormatError::InvalidFourCC("UYVY2".to_string()))
+    );
+    assert_eq!(FourCCType::try_from(0), Err(FormatError::UnknownFourCC(0)));
+    assert_eq!(
+        FormatError::InvalidFourCC("ABCD".to_string()).to_string(),
+        "\"ABCD\" is not a supported FourCC"
+    );
+}
+
+#[test]
+fn frame_format_round_trips() {
+    for &format in [
+        FrameFormatType::Progressive,
+        FrameFormatType::Interleaved,
+        FrameFormatType::Field0,
+        FrameFormatType::Field1,
+    ]
+    .iter()
+    {
+        assert_eq!(FrameFormatType::try_from(u32::from(format)), Ok(format));
+    }
+    assert_eq!(FrameFormatType::Field1.to_string(), "field 1");
+    assert_eq!(FrameFormatType::try_from(4), Err(FormatError::UnknownFrameFormat(4)));
+}
+
+#[test]
+fn format_properties() {
+    assert!(FourCCType::UYVA.has_alpha());
+    assert!(FourCCType::PA16.has_alpha());
+    assert!(!FourCCType::BGRX.has_alpha());
+    assert!(!FourCCType::UYVY.has_alpha());
+
+    assert!(FourCCType::NV12.is_planar());
+    assert!(FourCCType::UYVA.is_planar());
+    assert!(!FourCCType::UYVY.is_planar());
+    assert!(!FourCCType::RGBA.is_planar());
+
+    assert_eq!(FourCCType::I420.chroma_subsampling(), ChromaSubsampling::Cs420);
+    assert_eq!(FourCCType::P216.chroma_subsampling(), ChromaSubsampling::Cs422);
+    assert_eq!(FourCCType::BGRA.chroma_subsampling().to_string(), "4:4:4");
+
+    // Bits per pixel agree with the size of a tightly packed frame
+    for &format in ALL.iter() {
+        let stride = format.min_line_stride(16);
+        let size = format.buffer_size(16, 16, stride).unwrap();
+        assert_eq!(size * 8, 16 * 16 * format.bits_per_pixel() as usize, "{}", format);
+    }
+}
+
+#[test]
+fn tracker_reports_only_real_changes() {
+    let hd = VideoFormat {
+        width: 1920,
+        height: 1080,
+        frame_rate: FrameRate::FPS_29_97,
+        four_cc_type: FourCCType::UYVY,
+        frame_format_type: FrameFormatType::Progressive,
+    };
+    assert_eq!(hd.to_string(), "1920x1080 UYVY at 30000/1001 fps, progressive");
+
+    let mut tracker = FormatTracker::new();
+    assert_eq!(tracker.update(hd), Some(FormatChanged { old: None, new: hd }));
+    assert_eq!(tracker.update(hd), None);
+
+    let interleaved = VideoFormat {
+        frame_format_type: FrameFormatType::Interleaved,
+        ..hd
+    };
+    assert_eq!(
+        tracker.update(interleaved),
+        Some(FormatChanged {
+            old: Some(hd),
+            new: interleaved
+        })
+    );
+    assert_eq!(tracker.current(), Some(interleaved));
+
+    tracker.reset();
+    assert_eq!(tracker.current(), None);
+    assert_eq!(
+        tracker.update(interleaved),
+        Some(FormatChanged {
+            old: None,
+            new: interleaved
+        })
+    );
+}
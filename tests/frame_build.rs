@@ -0,0 +1,257 @@
+use ndi_sdk::send::{
+    create_ndi_send_audio_frame, create_ndi_send_video_frame, FourCCType, FrameBuildError, FrameFormatType,
+    NDISendAudioFrameBuilder,
+};
+use ndi_sdk::time::FrameRate;
+
+#[test]
+fn buffer_size_per_format() {
+    let cases = [
+        (FourCCType::UYVY, 1920 * 2, 1920 * 2 * 1080),
+        (FourCCType::BGRA, 1920 * 4, 1920 * 4 * 1080),
+        (FourCCType::RGBX, 1920 * 4 + 64, (1920 * 4 + 64) * 1080),
+        (FourCCType::YV12, 1920, 1920 * 1080 * 3 / 2),
+        (FourCCType::I420, 1920, 1920 * 1080 * 3 / 2),
+        (FourCCType::NV12, 1920, 1920 * 1080 * 3 / 2),
+        (FourCCType::UYVA, 1920 * 2, 1920 * 2 * 1080 + 1920 * 1080),
+        (FourCCType::P216, 1920 * 2, 1920 * 2 * 1080 * 2),
+        (FourCCType::PA16, 1920 * 2, 1920 * 2 * 1080 * 3),
+    ];
+    for (format, stride, size) in cases.iter() {
+        assert_eq!(format.buffer_size(1920, 1080, *stride), Ok(*size), "{:?}", format);
+    }
+}
+
+#[test]
+fn buffer_size_rounds_up_chroma_lines() {
+    assert_eq!(FourCCType::I420.buffer_size(4, 3, 4), Ok(4 * 3 + 2 * 2 * 2));
+    assert_eq!(FourCCType::NV12.buffer_size(4, 3, 4), Ok(4 * 3 + 4 * 2));
+}
+
+#[test]
+fn rejects_invalid_dimensions() {
+    assert_eq!(
+        FourCCType::BGRA.buffer_size(0, 1080, 0),
+        Err(FrameBuildError::InvalidDimensions { width: 0, height: 1080 })
+    );
+    assert_eq!(
+        FourCCType::UYVY.buffer_size(1919, 1080, 1920 * 2),
+        Err(FrameBuildError::InvalidDimensions {
+            width: 1919,
+            height: 1080
+        })
+    );
+    assert_eq!(FourCCType::BGRA.buffer_size(1919, 1080, 1919 * 4), Ok(1919 * 4 * 1080));
+}
+
+#[test]
+fn rejects_dimensions_that_overflow() {
+    assert_eq!(
+        FourCCType::BGRA.buffer_size(i32::MAX / 2, 2, i32::MAX),
+        Err(FrameBuildError::InvalidDimensions {
+            width: i32::MAX / 2,
+            height: 2
+        })
+    );
+    assert_eq!(FourCCType::BGRA.min_line_stride(i32::MAX / 2), i32::MAX);
+    assert_eq!(FourCCType::UYVY.min_line_stride(i32::MAX / 2), i32::MAX - 1);
+}
+
+#[test]
+fn rejects_short_stride() {
+    assert_eq!(
+        FourCCType::UYVY.buffer_size(1920, 1080, 1920),
+        Err(FrameBuildError::InvalidStride {
+            line_stride: 1920,
+            minimum: 1920 * 2
+        })
+    );
+}
+
+#[test]
+fn build_rejects_short_data() {
+    let res = create_ndi_send_video_frame(4, 4, FrameFormatType::Progressive)
+        .with_framerate(FrameRate::FPS_30)
+        .with_data(vec![0; 4 * 4], 4, FourCCType::NV12)
+        .build();
+    match res {
+        Err(FrameBuildError::InvalidDataLength { expected, actual }) => {
+            assert_eq!(expected, 4 * 4 * 3 / 2);
+            assert_eq!(actual, 4 * 4);
+        }
+        _ => panic!("expected an invalid data length"),
+    }
+
+    let res = create_ndi_send_video_frame(4, 4, FrameFormatType::Progressive)
+        .with_framerate(FrameRate::FPS_30)
+        .with_data(vec![0; 4 * 4 * 3 / 2], 4, FourCCType::NV12)
+        .build();
+    assert!(res.is_ok());
+}
+
+#[test]
+fn build_borrowed_checks_planar_size() {
+    let data = vec![0; 4 * 2 * 4];
+    let res = create_ndi_send_video_frame(4, 4, FrameFormatType::Progressive)
+        .with_framerate(FrameRate::FPS_30)
+        .build_borrowed(&data, 8, FourCCType::UYVA);
+    match res {
+        Err(FrameBuildError::InvalidDataLength { expected, .. }) => assert_eq!(expected, 8 * 4 + 4 * 4),
+        _ => panic!("expected an invalid data length"),
+    }
+}
+
+#[test]
+fn build_rejects_invalid_frame_rate() {
+    let builder = || {
+        create_ndi_send_video_frame(2, 2, FrameFormatType::Progressive).with_data(
+            vec![0; 2 * 2 * 4],
+            2 * 4,
+            FourCCType::BGRA,
+        )
+    };
+    assert_eq!(
+        builder().with_framerate(FrameRate::new(0, 0)).build().err(),
+        Some(FrameBuildError::InvalidFrameRate(FrameRate::new(0, 0)))
+    );
+    assert_eq!(
+        builder()
+            .with_framerate(FrameRate::new(25, -1))
+            .build_borrowed(&[0; 16], 8, FourCCType::BGRA)
+            .err(),
+        Some(FrameBuildError::InvalidFrameRate(FrameRate::new(25, -1)))
+    );
+    // A rate which was never set is rejected, rather than guessed
+    assert_eq!(
+        builder().build().err(),
+        Some(FrameBuildError::InvalidFrameRate(FrameRate::new(0, 0)))
+    );
+    assert!(builder().with_framerate(FrameRate::FPS_25).build().is_ok());
+}
+
+#[test]
+fn audio_build_checks_data_length() {
+    assert!(create_ndi_send_audio_frame(2, 48000)
+        .with_data(vec![0.0; 2 * 16], 16)
+        .build()
+        .is_ok());
+    assert_eq!(
+        create_ndi_send_audio_frame(2, 48000)
+            .with_data(vec![0.0; 16], 16)
+            .build()
+            .err(),
+        Some(FrameBuildError::InvalidDataLength {
+            expected: 32,
+            actual: 16
+        })
+    );
+    assert_eq!(
+        create_ndi_send_audio_frame(2, 48000)
+            .with_data(vec![0.0; 33], 16)
+            .build()
+            .err(),
+        Some(FrameBuildError::InvalidDataLength {
+            expected: 32,
+            actual: 33
+        })
+    );
+}
+
+#[test]
+fn audio_build_checks_layout() {
+    assert_eq!(
+        create_ndi_send_audio_frame(0, 48000).with_data(vec![], 0).build().err(),
+        Some(FrameBuildError::InvalidChannelCount { channel_count: 0 })
+    );
+    assert_eq!(
+        create_ndi_send_audio_frame(1, 48000)
+            .with_data(vec![], -1)
+            .build()
+            .err(),
+        Some(FrameBuildError::InvalidSampleCount { sample_count: -1 })
+    );
+    assert_eq!(
+        create_ndi_send_audio_frame(2, 48000)
+            .with_data(vec![0.0; 2 * 15], 16)
+            .with_channel_stride(15 * 4)
+            .build()
+            .err(),
+        Some(FrameBuildError::InvalidChannelStride {
+            channel_stride: 60,
+            minimum: 64
+        })
+    );
+    assert_eq!(
+        create_ndi_send_audio_frame(2, 48000)
+            .with_data(vec![0.0; 2 * 17], 16)
+            .with_channel_stride(16 * 4 + 2)
+            .build()
+            .err(),
+        Some(FrameBuildError::InvalidChannelStride {
+            channel_stride: 66,
+            minimum: 64
+        })
+    );
+    assert!(create_ndi_send_audio_frame(2, 48000)
+        .with_channel_stride(20 * 4)
+        .with_data(vec![0.0; 2 * 20], 16)
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn audio_from_channels() {
+    assert!(create_ndi_send_audio_frame(1, 48000)
+        .with_channels(vec![vec![0.0; 8], vec![0.0; 8], vec![0.0; 8]])
+        .build()
+        .is_ok());
+    assert_eq!(
+        create_ndi_send_audio_frame(2, 48000)
+            .with_channels(vec![vec![0.0; 8], vec![0.0; 7]])
+            .build()
+            .err(),
+        Some(FrameBuildError::MismatchedChannelLength {
+            channel: 1,
+            expected: 8,
+            actual: 7
+        })
+    );
+    assert_eq!(
+        create_ndi_send_audio_frame(2, 48000)
+            .with_channels(vec![])
+            .build()
+            .err(),
+        Some(FrameBuildError::InvalidChannelCount { channel_count: 0 })
+    );
+
+    // Replacing mismatched channels replaces the error they caused
+    assert!(create_ndi_send_audio_frame(2, 48000)
+        .with_channels(vec![vec![0.0; 8], vec![0.0; 7]])
+        .with_channels(vec![vec![0.0; 8], vec![0.0; 8]])
+        .build()
+        .is_ok());
+    assert!(create_ndi_send_audio_frame(2, 48000)
+        .with_channels(vec![vec![0.0; 8], vec![0.0; 7]])
+        .with_data(vec![0.0; 2 * 8], 8)
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn audio_from_interleaved() {
+    assert!(NDISendAudioFrameBuilder::from_interleaved(&[0.0; 6], 3, 48000)
+        .build()
+        .is_ok());
+    assert_eq!(
+        NDISendAudioFrameBuilder::from_interleaved(&[0.0; 7], 3, 48000)
+            .build()
+            .err(),
+        Some(FrameBuildError::InvalidDataLength { expected: 9, actual: 7 })
+    );
+    assert_eq!(
+        NDISendAudioFrameBuilder::from_interleaved(&[0.0; 6], 0, 48000)
+            .build()
+            .err(),
+        Some(FrameBuildError::InvalidChannelCount { channel_count: 0 })
+    );
+}
@@ -0,0 +1,196 @@
+use ndi_sdk::audio::meter::{to_dbfs, AudioMeter};
+use std::f64::consts::PI;
+
+fn sine(frequency: f64, amplitude: f64, phase: f64, sample_rate: i32, seconds: f64) -> Vec<f32> {
+    let len = (sample_rate as f64 * seconds) as usize;
+    (0..len)
+        .map(|i| (amplitude * (2.0 * PI * frequency * i as f64 / sample_rate as f64 + phase).sin()) as f32)
+        .collect()
+}
+
+fn assert_near(actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "expected {} +/- {}, got {}",
+        expected,
+        tolerance,
+        actual
+    );
+}
+
+#[test]
+fn full_scale_sine_in_one_channel_is_minus_3_lufs() {
+    // The calibration signal from BS.1770: a 997 Hz sine at 0 dBFS in one channel measures -3.01 LKFS
+    for &sample_rate in [44100, 48000, 96000].iter() {
+        let mut meter = AudioMeter::new();
+        let signal = sine(997.0, 1.0, 0.0, sample_rate, 3.5);
+        meter.process_planar(vec![&signal[..]], sample_rate);
+
+        let snapshot = meter.snapshot();
+        assert_eq!(snapshot.sample_rate, sample_rate);
+        assert_near(snapshot.momentary_lufs, -3.01, 0.05);
+        assert_near(snapshot.short_term_lufs, -3.01, 0.05);
+        assert_near(snapshot.channels[0].momentary_lufs, -3.01, 0.05);
+    }
+}
+
+#[test]
+fn stereo_reference_level() {
+    // EBU Tech 3341: a stereo 1 kHz sine at -23 dBFS in both channels measures -23 LUFS
+    let amplitude = 10f64.powf(-23.0 / 20.0);
+    let signal = sine(1000.0, amplitude, 0.0, 48000, 4.0);
+
+    // Fed in frames of varying size, to cross block boundaries at different points
+    let mut meter = AudioMeter::new();
+    let mut pos = 0;
+    for len in [1000, 1921, 480, 4800].iter().cycle() {
+        let end = (pos + len).min(signal.len());
+        meter.process_planar(vec![&signal[pos..end], &signal[pos..end]], 48000);
+        pos = end;
+        if pos == signal.len() {
+            break;
+        }
+    }
+
+    let snapshot = meter.snapshot();
+    assert_near(snapshot.momentary_lufs, -23.0, 0.1);
+    assert_near(snapshot.short_term_lufs, -23.0, 0.1);
+    // Each channel alone is half as loud
+    assert_near(snapshot.channels[1].short_term_lufs, -26.01, 0.1);
+}
+
+#[test]
+fn channel_weights() {
+    let signal = sine(1000.0, 0.1, 0.0, 48000, 3.0);
+    let silence = vec![0.0; signal.len()];
+
+    let mut meter = AudioMeter::new();
+    meter.process_planar(vec![&signal[..], &silence[..]], 48000);
+    let unweighted = meter.snapshot().short_term_lufs;
+
+    // Surround channels count for 1.41 times as much
+    let mut meter = AudioMeter::new();
+    meter.set_channel_weight(0, 1.41);
+    meter.process_planar(vec![&signal[..], &silence[..]], 48000);
+    assert_near(
+        meter.snapshot().short_term_lufs - unweighted,
+        10.0 * 1.41f64.log10(),
+        0.01,
+    );
+
+    // And the LFE channel not at all
+    let mut meter = AudioMeter::new();
+    meter.set_channel_weight(1, 0.0);
+    meter.process_planar(vec![&silence[..], &signal[..]], 48000);
+    assert_eq!(meter.snapshot().short_term_lufs, f64::NEG_INFINITY);
+}
+
+#[test]
+fn windows_start_silent_and_follow_the_signal() {
+    let signal = sine(1000.0, 0.5, 0.0, 48000, 0.4);
+    let mut meter = AudioMeter::new();
+
+    let snapshot = meter.snapshot();
+    assert!(snapshot.channels.is_empty());
+
+    meter.process_planar(vec![&signal[..]], 48000);
+    let snapshot = meter.snapshot();
+    // The momentary window is full, but the short-term window is mostly silence
+    let momentary = snapshot.momentary_lufs;
+    assert_near(
+        snapshot.short_term_lufs,
+        momentary + 10.0 * (4.0f64 / 30.0).log10(),
+        0.2,
+    );
+
+    // 400 ms of silence empties the momentary window, apart from the tail of the filters, but not the short-term one
+    let silence = vec![0.0; signal.len()];
+    meter.process_planar(vec![&silence[..]], 48000);
+    let snapshot = meter.snapshot();
+    assert!(
+        snapshot.momentary_lufs < momentary - 40.0,
+        "{}",
+        snapshot.momentary_lufs
+    );
+    assert!(snapshot.short_term_lufs > -30.0);
+    assert_eq!(snapshot.channels[0].rms, 0.0);
+}
+
+#[test]
+fn peak_and_rms() {
+    let signal = sine(1000.0, 0.5, 0.0, 48000, 0.5);
+    let quieter = sine(1000.0, 0.25, 0.0, 48000, 0.5);
+    let mut meter = AudioMeter::new();
+    meter.process_planar(vec![&signal[..], &quieter[..]], 48000);
+
+    let snapshot = meter.snapshot();
+    assert_near(snapshot.channels[0].peak as f64, 0.5, 1e-4);
+    assert_near(snapshot.channels[1].peak as f64, 0.25, 1e-4);
+    assert_near(snapshot.channels[0].rms as f64, 0.5 / 2f64.sqrt(), 1e-3);
+    assert_near(to_dbfs(snapshot.channels[1].peak) as f64, -12.04, 0.01);
+
+    // Peaks are held until reset
+    meter.process_planar(vec![&quieter[..], &quieter[..]], 48000);
+    assert_near(meter.snapshot().channels[0].peak as f64, 0.5, 1e-4);
+    meter.reset_peaks();
+    meter.process_planar(vec![&quieter[..], &quieter[..]], 48000);
+    assert_near(meter.snapshot().channels[0].peak as f64, 0.25, 1e-4);
+}
+
+#[test]
+fn true_peak_finds_peaks_between_samples() {
+    // A sine at a quarter of the sample rate, sampled 45 degrees away from its peaks, never has a sample above 0.707
+    for &sample_rate in [48000, 96000].iter() {
+        let signal = sine(sample_rate as f64 / 4.0, 1.0, PI / 4.0, sample_rate, 0.1);
+        let mut meter = AudioMeter::new();
+        meter.process_planar(vec![&signal[..]], sample_rate);
+
+        let levels = &meter.snapshot().channels[0];
+        assert_near(levels.peak as f64, 0.5f64.sqrt(), 1e-4);
+        assert_near(levels.true_peak as f64, 1.0, 0.05);
+    }
+
+    // Without any peaks between samples, the true peak is close to the sample peak
+    let signal = sine(1000.0, 0.5, 0.0, 48000, 0.1);
+    let mut meter = AudioMeter::new();
+    meter.process_planar(vec![&signal[..]], 48000);
+    let levels = &meter.snapshot().channels[0];
+    assert_near(levels.true_peak as f64, levels.peak as f64, 0.01);
+}
+
+#[test]
+fn reader_sees_snapshots_from_another_thread() {
+    let mut meter = AudioMeter::new();
+    let reader = meter.reader();
+
+    let handle = std::thread::spawn(move || {
+        let signal = sine(1000.0, 0.5, 0.0, 48000, 0.5);
+        meter.process_planar(vec![&signal[..]], 48000);
+        meter
+    });
+    let mut meter = handle.join().unwrap();
+
+    let snapshot = reader.snapshot();
+    assert_eq!(snapshot.channels.len(), 1);
+    assert_near(snapshot.channels[0].peak as f64, 0.5, 1e-4);
+
+    // Peaks reset from the reader are cleared when the next frame is processed
+    reader.reset_peaks();
+    let quieter = sine(1000.0, 0.25, 0.0, 48000, 0.1);
+    meter.process_planar(vec![&quieter[..]], 48000);
+    assert_near(reader.snapshot().channels[0].peak as f64, 0.25, 1e-4);
+}
+
+#[test]
+fn format_changes_reset_the_meter() {
+    let signal = sine(1000.0, 0.5, 0.0, 48000, 0.5);
+    let mut meter = AudioMeter::new();
+    meter.process_planar(vec![&signal[..], &signal[..]], 48000);
+
+    let quieter = sine(1000.0, 0.25, 0.0, 44100, 0.1);
+    meter.process_planar(vec![&quieter[..]], 44100);
+    let snapshot = meter.snapshot();
+    assert_eq!(snapshot.sample_rate, 44100);
+    assert_eq!(snapshot.channels.len(), 1);
+    assert_near(snapshot.channels[0].peak as f64, 0.25, 1e-4);
+}
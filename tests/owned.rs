@@ -0,0 +1,136 @@
+#![cfg(feature = "dynamic-link")]
+
+mod common;
+
+use ndi_sdk::format::{FourCCType, FrameFormatType};
+use ndi_sdk::receive::{AudioFrame, ReceiveCaptureResult, ReceiveInstance, ReceiveInstanceExt, VideoFrame};
+use ndi_sdk::send::{create_ndi_send_audio_frame, create_ndi_send_video_frame};
+use ndi_sdk::time::{FrameRate, Timecode, Timestamp};
+use std::sync::Arc;
+
+fn capture_video(receiver: &Arc<ReceiveInstance>) -> VideoFrame {
+    match receiver.receive_capture(true, false, false, 0).expect("capture") {
+        ReceiveCaptureResult::Video(frame) => frame,
+        _ => panic!("expected a video frame"),
+    }
+}
+
+fn capture_audio(receiver: &Arc<ReceiveInstance>) -> AudioFrame {
+    match receiver.receive_capture(false, true, false, 0).expect("capture") {
+        ReceiveCaptureResult::Audio(frame) => frame,
+        _ => panic!("expected an audio frame"),
+    }
+}
+
+#[test]
+fn owned_video_keeps_every_plane_and_property() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("owned_video".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "owned_video");
+
+    // A luma plane of 8x4 with a stride of 12, followed by a half height chroma plane
+    let data: Vec<u8> = (0..12 * 6).map(|i| i as u8).collect();
+    let frame = create_ndi_send_video_frame(8, 4, FrameFormatType::Interleaved)
+        .with_framerate(FrameRate::FPS_29_97)
+        .with_aspect_ratio(16.0 / 9.0)
+        .with_timecode(Timecode::Value(1234))
+        .with_timestamp(Timestamp::Value(5678))
+        .with_metadata("<owned/>".to_string())
+        .with_data(data.clone(), 12, FourCCType::NV12)
+        .build()
+        .unwrap();
+    sender.send_video(frame);
+
+    let frame = capture_video(&receiver);
+    let owned = frame.to_owned();
+    drop(frame);
+
+    assert_eq!((owned.width, owned.height), (8, 4));
+    assert_eq!(owned.frame_rate, FrameRate::new(30000, 1001));
+    assert_eq!(owned.picture_aspect_ratio, 16.0 / 9.0);
+    assert_eq!(owned.four_cc_type, FourCCType::NV12);
+    assert_eq!(owned.frame_format_type, FrameFormatType::Interleaved);
+    assert_eq!(
+        (owned.timecode, owned.timestamp),
+        (Timecode::Value(1234), Timestamp::Value(5678))
+    );
+    assert_eq!(owned.line_stride_in_bytes, 12);
+    assert_eq!(owned.metadata.as_deref(), Some("<owned/>"));
+    assert_eq!(owned.data, data);
+    assert!(owned.planes().is_some());
+}
+
+#[test]
+fn owned_video_can_be_sent_on() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("republish_video_in".to_string(), false, false)
+        .expect("create sender");
+    let republisher = instance
+        .create_send_instance("republish_video_out".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "republish_video_in");
+    let output = common::loopback_receiver(&instance, "republish_video_out");
+
+    let frame = create_ndi_send_video_frame(4, 2, FrameFormatType::Field1)
+        .with_framerate(FrameRate::FPS_25)
+        .with_timecode(Timecode::Value(40_000))
+        .with_metadata("<first/>".to_string())
+        .with_data(vec![7; 4 * 4 * 2], 4 * 4, FourCCType::BGRA)
+        .build()
+        .unwrap();
+    sender.send_video(frame);
+
+    let owned = capture_video(&receiver).to_owned();
+    republisher.send_video(owned.clone().into_send_frame().unwrap());
+    let stats = common::sender_stats("republish_video_out");
+    assert_eq!(stats.video_frames, 1);
+    assert_eq!(stats.video_checksum, 7 * 4 * 4 * 2);
+    assert_eq!(stats.video_metadata, "<first/>");
+
+    // Nothing is lost on the way through
+    assert_eq!(capture_video(&output).to_owned(), owned);
+}
+
+#[test]
+fn owned_audio_drops_padding() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("owned_audio".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "owned_audio");
+
+    let frame = create_ndi_send_audio_frame(2, 48000)
+        .with_channels(vec![vec![0.1, 0.2, 0.3], vec![-0.1, -0.2, -0.3]])
+        .with_timecode(Timecode::Value(100))
+        .with_timestamp(Timestamp::Value(200))
+        .with_metadata("<audio/>".to_string())
+        .build()
+        .unwrap();
+    sender.send_audio(frame);
+
+    let frame = capture_audio(&receiver);
+    assert_eq!(frame.metadata.as_deref(), Some("<audio/>"));
+    let owned = frame.to_owned();
+    drop(frame);
+
+    assert_eq!(owned.sample_rate, 48000);
+    assert_eq!((owned.channel_count, owned.sample_count), (2, 3));
+    assert_eq!(
+        (owned.timecode, owned.timestamp),
+        (Timecode::Value(100), Timestamp::Value(200))
+    );
+    assert_eq!(owned.metadata.as_deref(), Some("<audio/>"));
+    // The stub pads the received channels to 16 samples, which are not copied
+    assert_eq!(owned.data, [0.1, 0.2, 0.3, -0.1, -0.2, -0.3]);
+    assert_eq!(owned.channel(1), [-0.1, -0.2, -0.3]);
+    assert_eq!(owned.channels().len(), 2);
+
+    sender.send_audio(owned.clone().into_send_frame().unwrap());
+    let stats = common::sender_stats("owned_audio");
+    assert_eq!(stats.audio_frames, 2);
+    assert_eq!(stats.audio_metadata, "<audio/>");
+    assert_eq!(capture_audio(&receiver).to_owned(), owned);
+}
@@ -0,0 +1,141 @@
+use ndi_sdk::planes::VideoPlanes;
+use ndi_sdk::receive::FourCCType;
+
+/// A frame where every byte is its offset from the start of the buffer, modulo 251
+fn numbered(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn packed_rows_skip_padding() {
+    let data = numbered(FourCCType::BGRA.buffer_size(3, 2, 16).unwrap());
+    assert_eq!(data.len(), 32);
+
+    match VideoPlanes::new(&data, FourCCType::BGRA, 3, 2, 16).unwrap() {
+        VideoPlanes::Packed(plane) => {
+            assert_eq!(plane.line_stride(), 16);
+            assert_eq!(plane.row_bytes(), 12);
+            assert_eq!(plane.height(), 2);
+            let rows: Vec<_> = plane.rows().collect();
+            assert_eq!(rows, vec![&data[0..12], &data[16..28]]);
+            assert_eq!(plane.row(1), &data[16..28]);
+        }
+        p => panic!("unexpected planes: {:?}", p),
+    }
+}
+
+#[test]
+fn i420_and_yv12_chroma_order() {
+    let data = numbered(FourCCType::I420.buffer_size(4, 4, 8).unwrap());
+    assert_eq!(data.len(), 8 * 4 + 2 * 4 * 2);
+
+    match VideoPlanes::new(&data, FourCCType::I420, 4, 4, 8).unwrap() {
+        VideoPlanes::Planar { y, u, v } => {
+            assert_eq!(y.row(3), &data[24..28]);
+            assert_eq!(u.line_stride(), 4);
+            assert_eq!(u.row_bytes(), 2);
+            assert_eq!(u.height(), 2);
+            assert_eq!(u.row(0), &data[32..34]);
+            assert_eq!(v.row(0), &data[40..42]);
+        }
+        p => panic!("unexpected planes: {:?}", p),
+    }
+
+    match VideoPlanes::new(&data, FourCCType::YV12, 4, 4, 8).unwrap() {
+        VideoPlanes::Planar { u, v, .. } => {
+            assert_eq!(v.row(0), &data[32..34]);
+            assert_eq!(u.row(0), &data[40..42]);
+        }
+        p => panic!("unexpected planes: {:?}", p),
+    }
+}
+
+#[test]
+fn nv12_has_interleaved_chroma() {
+    let data = numbered(FourCCType::NV12.buffer_size(4, 3, 4).unwrap());
+    assert_eq!(data.len(), 4 * 3 + 4 * 2);
+
+    match VideoPlanes::new(&data, FourCCType::NV12, 4, 3, 4).unwrap() {
+        VideoPlanes::SemiPlanar { y, uv } => {
+            assert_eq!(y.height(), 3);
+            assert_eq!(uv.height(), 2);
+            assert_eq!(uv.row_bytes(), 4);
+            assert_eq!(uv.rows().collect::<Vec<_>>(), vec![&data[12..16], &data[16..20]]);
+        }
+        p => panic!("unexpected planes: {:?}", p),
+    }
+}
+
+#[test]
+fn uyva_has_alpha_plane() {
+    let data = numbered(FourCCType::UYVA.buffer_size(2, 2, 8).unwrap());
+    assert_eq!(data.len(), 8 * 2 + 2 * 2);
+
+    match VideoPlanes::new(&data, FourCCType::UYVA, 2, 2, 8).unwrap() {
+        VideoPlanes::PackedAlpha { packed, alpha } => {
+            assert_eq!(packed.row_bytes(), 4);
+            assert_eq!(packed.row(1), &data[8..12]);
+            assert_eq!(alpha.line_stride(), 2);
+            assert_eq!(alpha.rows().collect::<Vec<_>>(), vec![&data[16..18], &data[18..20]]);
+        }
+        p => panic!("unexpected planes: {:?}", p),
+    }
+}
+
+#[test]
+fn short_data_is_rejected() {
+    let data = vec![0; 8 * 4];
+    assert!(VideoPlanes::new(&data, FourCCType::NV12, 8, 4, 8).is_none());
+    assert!(VideoPlanes::new(&data, FourCCType::UYVY, 4, 4, 8).is_some());
+    assert!(VideoPlanes::new(&data, FourCCType::UYVY, 0, 4, 8).is_none());
+}
+
+#[test]
+fn short_stride_is_rejected() {
+    let data = vec![0; 8];
+    assert!(VideoPlanes::new(&data, FourCCType::UYVY, 10, 2, 4).is_none());
+    assert!(VideoPlanes::new(&data, FourCCType::NV12, 4, 2, 2).is_none());
+    assert!(VideoPlanes::new(&data, FourCCType::UYVY, 2, 2, 4).is_some());
+}
+
+#[test]
+fn p216_has_full_height_16bit_chroma() {
+    let mut data = vec![0; FourCCType::P216.buffer_size(2, 2, 8).unwrap()];
+    assert_eq!(data.len(), 8 * 2 * 2);
+    // The second luma sample of the first row, and the first chroma sample of the second row
+    data[2..4].copy_from_slice(&0x1234u16.to_le_bytes());
+    data[24..26].copy_from_slice(&0xffc0u16.to_le_bytes());
+
+    match VideoPlanes::new(&data, FourCCType::P216, 2, 2, 8).unwrap() {
+        VideoPlanes::SemiPlanar { y, uv } => {
+            assert_eq!(y.row_bytes(), 4);
+            assert_eq!(uv.height(), 2);
+            assert_eq!(y.row_u16(0).collect::<Vec<_>>(), vec![0, 0x1234]);
+            assert_eq!(uv.row_u16(1).collect::<Vec<_>>(), vec![0xffc0, 0]);
+        }
+        p => panic!("unexpected planes: {:?}", p),
+    }
+}
+
+#[test]
+fn pa16_has_alpha_plane() {
+    let data = numbered(FourCCType::PA16.buffer_size(2, 2, 4).unwrap());
+    assert_eq!(data.len(), 4 * 2 * 3);
+
+    match VideoPlanes::new(&data, FourCCType::PA16, 2, 2, 4).unwrap() {
+        VideoPlanes::SemiPlanarAlpha { y, uv, alpha } => {
+            assert_eq!(y.row(1), &data[4..8]);
+            assert_eq!(uv.row(0), &data[8..12]);
+            assert_eq!(alpha.row(1), &data[20..24]);
+        }
+        p => panic!("unexpected planes: {:?}", p),
+    }
+}
+
+#[test]
+fn sixteen_bit_four_cc_values() {
+    use std::convert::TryFrom;
+
+    assert_eq!(FourCCType::try_from(u32::from_le_bytes(*b"P216")), Ok(FourCCType::P216));
+    assert_eq!(FourCCType::try_from(u32::from_le_bytes(*b"PA16")), Ok(FourCCType::PA16));
+}
@@ -0,0 +1,319 @@
+#![cfg(feature = "dynamic-link")]
+
+mod common;
+
+use ndi_sdk::format::VideoFormat;
+use ndi_sdk::receive::{Captured, FormatTracker, MetadataFrame, ReceiveCaptureResult, ReceiveInstanceExt};
+use ndi_sdk::send::{create_ndi_send_audio_frame, create_ndi_send_video_frame, FourCCType, FrameFormatType};
+use ndi_sdk::time::{FrameRate, Timecode};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn frames_are_freed_before_the_receiver_is_destroyed() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("free_order".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "free_order");
+
+    sender.send_audio_interleaved_f32(&[0.5; 8], 2, 48000).unwrap();
+    let frame = create_ndi_send_video_frame(2, 2, FrameFormatType::Progressive)
+        .with_framerate(FrameRate::FPS_30)
+        .with_data(vec![1; 2 * 2 * 4], 2 * 4, FourCCType::BGRA)
+        .build()
+        .unwrap();
+    sender.send_video(frame);
+
+    let audio = receiver.receive_capture(false, true, false, 0).unwrap();
+    let video = receiver.receive_capture(true, false, false, 0).unwrap();
+    assert!(matches!(audio, ReceiveCaptureResult::Audio(_)));
+    assert!(matches!(video, ReceiveCaptureResult::Video(_)));
+
+    // The frames keep the receiver alive, so it is only destroyed once they have both been freed
+    drop(receiver);
+    drop(audio);
+    drop(video);
+    assert_eq!(common::receivers_destroyed_early(), 0);
+}
+
+#[test]
+fn frames_are_captured_and_dropped_across_threads() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("threaded_capture".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "threaded_capture");
+
+    for i in 0..400 {
+        sender.send_audio_interleaved_f32(&[i as f32; 4], 1, 48000).unwrap();
+    }
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let receiver = Arc::clone(&receiver);
+            thread::spawn(move || {
+                let mut frames = Vec::new();
+                while let ReceiveCaptureResult::Audio(frame) = receiver.receive_capture(false, true, false, 0).unwrap()
+                {
+                    frames.push(frame);
+                    // Drop some frames on a different thread to the one which captured them
+                    if frames.len() > 8 {
+                        let old: Vec<_> = frames.drain(..4).collect();
+                        thread::spawn(move || drop(old)).join().unwrap();
+                    }
+                }
+                frames.len()
+            })
+        })
+        .collect();
+    drop(receiver);
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(common::receivers_destroyed_early(), 0);
+}
+
+#[test]
+fn frame_data_outlives_the_receiver() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("outlives_receiver".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "outlives_receiver");
+
+    let pixels: Vec<u8> = (0..4 * 2 * 4).map(|i| i as u8).collect();
+    let frame = create_ndi_send_video_frame(4, 2, FrameFormatType::Progressive)
+        .with_framerate(FrameRate::FPS_30)
+        .with_data(pixels.clone(), 4 * 4, FourCCType::RGBA)
+        .build()
+        .unwrap();
+    sender.send_video(frame);
+    sender
+        .send_audio_interleaved_f32(&[0.25, -0.25, 0.5, -0.5], 2, 48000)
+        .unwrap();
+
+    let video = match receiver.receive_capture(true, false, false, 0).unwrap() {
+        ReceiveCaptureResult::Video(frame) => frame,
+        _ => panic!("expected a video frame"),
+    };
+    let audio = match receiver.receive_capture(false, true, false, 0).unwrap() {
+        ReceiveCaptureResult::Audio(frame) => frame,
+        _ => panic!("expected an audio frame"),
+    };
+
+    // Dropping every other handle to the receiver leaves the frames, and their data, intact
+    drop(receiver);
+    drop(sender);
+    assert_eq!(&*video.lock_data(), &pixels[..]);
+    assert_eq!(video.to_owned().data, pixels);
+    assert_eq!(audio.lock_data().channel(1), [-0.25, -0.5]);
+    assert_eq!(audio.to_interleaved_f32(), [0.25, -0.25, 0.5, -0.5]);
+
+    drop(video);
+    drop(audio);
+    assert_eq!(common::receivers_destroyed_early(), 0);
+}
+
+#[test]
+fn frames_without_data_are_empty() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("empty_frames".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "empty_frames");
+
+    // The stub gives a null data pointer for a frame without any samples
+    let frame = create_ndi_send_audio_frame(2, 48000)
+        .with_channels(vec![Vec::new(), Vec::new()])
+        .build()
+        .unwrap();
+    sender.send_audio(frame);
+
+    let audio = match receiver.receive_capture(false, true, false, 0).unwrap() {
+        ReceiveCaptureResult::Audio(frame) => frame,
+        _ => panic!("expected an audio frame"),
+    };
+    let data = audio.lock_data();
+    assert!(data.is_empty());
+    assert_eq!(data.channels().collect::<Vec<_>>(), [&[] as &[f32], &[]]);
+    assert!(audio.to_interleaved_i16(0).is_empty());
+    assert_eq!(audio.to_owned().channel_count, 2);
+}
+
+#[test]
+fn split_receivers_capture_only_their_own_type() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("split".to_string(), false, false)
+        .expect("create sender");
+    let (video, audio, metadata) = common::loopback_receiver(&instance, "split").split();
+
+    common::queue_metadata("split", "<hello/>", 42);
+    sender.send_audio_interleaved_f32(&[0.5; 4], 2, 48000).unwrap();
+    let frame = create_ndi_send_video_frame(2, 2, FrameFormatType::Progressive)
+        .with_framerate(FrameRate::FPS_30)
+        .with_data(vec![3; 2 * 2 * 4], 2 * 4, FourCCType::BGRA)
+        .build()
+        .unwrap();
+    sender.send_video(frame);
+
+    // Each handle only takes frames of its own type, whatever else is waiting
+    let metadata_frame = metadata.capture(0).unwrap().frame().expect("metadata frame");
+    assert_eq!(
+        metadata_frame,
+        MetadataFrame {
+            timecode: Timecode::Value(42),
+            data: "<hello/>".to_string()
+        }
+    );
+    assert!(matches!(metadata.capture(0), Ok(Captured::None)));
+
+    let audio_frame = audio.capture(0).unwrap().frame().expect("audio frame");
+    assert_eq!(audio_frame.channel_count, 2);
+    assert!(matches!(audio.capture(0), Ok(Captured::None)));
+
+    let video_frame = video.capture(0).unwrap().frame().expect("video frame");
+    assert_eq!(video_frame.width, 2);
+    assert!(matches!(video.capture(0), Ok(Captured::None)));
+}
+
+#[test]
+fn status_changes_are_reported() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("status".to_string(), false, false)
+        .expect("create sender");
+    let receiver = common::loopback_receiver(&instance, "status");
+
+    common::queue_status_change("status");
+    common::queue_metadata("status", "<after/>", 0);
+    assert!(matches!(
+        receiver.receive_capture(true, true, true, 0),
+        Ok(ReceiveCaptureResult::StatusChange)
+    ));
+    assert!(matches!(
+        receiver.receive_capture(true, true, true, 0),
+        Ok(ReceiveCaptureResult::Metadata(_))
+    ));
+
+    // Each split handle tells a status change apart from a timeout
+    let (video, audio, metadata) = receiver.split();
+    for _ in 0..3 {
+        common::queue_status_change("status");
+    }
+    assert!(matches!(video.capture(0), Ok(Captured::StatusChange)));
+    assert!(matches!(audio.capture(0), Ok(Captured::StatusChange)));
+    assert!(matches!(metadata.capture(0), Ok(Captured::StatusChange)));
+    assert!(matches!(video.capture(0), Ok(Captured::None)));
+
+    // And the frames after it are still captured
+    sender.send_audio_interleaved_f32(&[0.5; 4], 2, 48000).unwrap();
+    common::queue_status_change("status");
+    assert!(matches!(audio.capture(0), Ok(Captured::StatusChange)));
+    assert!(matches!(audio.capture(0), Ok(Captured::Frame(_))));
+    assert!(matches!(audio.capture(0), Ok(Captured::None)));
+}
+
+#[test]
+fn split_receivers_run_on_their_own_threads() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("split_threads".to_string(), false, false)
+        .expect("create sender");
+    let (video, audio, metadata) = common::loopback_receiver(&instance, "split_threads").split();
+
+    for i in 0..20 {
+        common::queue_metadata("split_threads", "<tick/>", i);
+        sender.send_audio_interleaved_f32(&[0.5; 4], 2, 48000).unwrap();
+        sender.send_video(
+            create_ndi_send_video_frame(2, 2, FrameFormatType::Progressive)
+                .with_framerate(FrameRate::FPS_30)
+                .with_data(vec![3; 2 * 2 * 4], 2 * 4, FourCCType::BGRA)
+                .build()
+                .unwrap(),
+        );
+    }
+
+    // The receiver itself has been dropped, so only the handles keep it alive
+    let video = thread::spawn(move || (0..20).filter(|_| video.capture(0).unwrap().frame().is_some()).count());
+    let audio = thread::spawn(move || (0..20).filter(|_| audio.capture(0).unwrap().frame().is_some()).count());
+    let metadata = thread::spawn(move || {
+        (0..20)
+            .map(|_| {
+                metadata
+                    .capture(0)
+                    .unwrap()
+                    .frame()
+                    .expect("metadata frame")
+                    .timecode
+                    .value()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(video.join().unwrap(), 20);
+    assert_eq!(audio.join().unwrap(), 20);
+    assert_eq!(metadata.join().unwrap(), (0..20).collect::<Vec<_>>());
+    assert_eq!(common::receivers_destroyed_early(), 0);
+}
+
+#[test]
+fn format_changes_are_tracked_across_frames() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("format_changes".to_string(), false, false)
+        .expect("create sender");
+    let (video, _, _) = common::loopback_receiver(&instance, "format_changes").split();
+
+    let send = |width: i32, frame_rate: FrameRate, format: FourCCType| {
+        let frame = create_ndi_send_video_frame(width, 2, FrameFormatType::Progressive)
+            .with_framerate(frame_rate)
+            .with_data(vec![0; width as usize * 2 * 4], width * 4, format)
+            .build()
+            .unwrap();
+        sender.send_video(frame);
+    };
+    send(4, FrameRate::FPS_25, FourCCType::BGRA);
+    send(4, FrameRate::FPS_25, FourCCType::BGRA);
+    send(4, FrameRate::FPS_50, FourCCType::BGRA);
+    // A status change between frames does not end the stream
+    common::queue_status_change("format_changes");
+    send(8, FrameRate::FPS_50, FourCCType::BGRA);
+    send(8, FrameRate::FPS_50, FourCCType::BGRX);
+    send(8, FrameRate::FPS_50, FourCCType::BGRX);
+
+    let mut tracker = FormatTracker::new();
+    let mut changes = Vec::new();
+    let mut status_changes = 0;
+    loop {
+        match video.capture(0).unwrap() {
+            Captured::Frame(frame) => {
+                if let Some(change) = tracker.process(&frame) {
+                    assert_eq!(change.new, frame.format());
+                    changes.push((change.old.map(|f| f.width), change.new));
+                }
+            }
+            Captured::StatusChange => status_changes += 1,
+            Captured::None => break,
+        }
+    }
+    assert_eq!(status_changes, 1);
+
+    let format = |width: i32, frame_rate: FrameRate, four_cc_type: FourCCType| VideoFormat {
+        width,
+        height: 2,
+        frame_rate,
+        four_cc_type,
+        frame_format_type: FrameFormatType::Progressive,
+    };
+    assert_eq!(
+        changes,
+        [
+            (None, format(4, FrameRate::FPS_25, FourCCType::BGRA)),
+            (Some(4), format(4, FrameRate::FPS_50, FourCCType::BGRA)),
+            (Some(4), format(8, FrameRate::FPS_50, FourCCType::BGRA)),
+            (Some(8), format(8, FrameRate::FPS_50, FourCCType::BGRX)),
+        ]
+    );
+}
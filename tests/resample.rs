@@ -0,0 +1,227 @@
+use ndi_sdk::audio::matrix::ChannelMatrix;
+use ndi_sdk::audio::resample::{Resampler, MAX_SAMPLE_RATE, MIN_SAMPLE_RATE};
+use ndi_sdk::audio::AudioBuffer;
+use ndi_sdk::time::{Timecode, Timestamp};
+use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+fn sine(frequency: f64, sample_rate: i32, start: usize, len: usize) -> Vec<f32> {
+    (start..start + len)
+        .map(|i| (0.5 * (2.0 * PI * frequency * i as f64 / sample_rate as f64).sin()) as f32)
+        .collect()
+}
+
+fn buffer(sample_rate: i32, timecode: i64, channels: Vec<Vec<f32>>) -> AudioBuffer {
+    AudioBuffer {
+        sample_rate,
+        timecode: Timecode::Value(timecode),
+        timestamp: Timestamp::Value(timecode + 5),
+        channels,
+    }
+}
+
+/// Resample a stereo sine in frames of varying sizes, returning the output buffers
+fn resample_sine(frequency: f64, input_rate: i32, output_rate: i32, seconds: f64) -> Vec<AudioBuffer> {
+    let total = (input_rate as f64 * seconds) as usize;
+    let mut resampler = Resampler::new(output_rate);
+    let mut res = Vec::new();
+    let mut pos = 0;
+    for len in [1024, 7, 480, 1921, 3000].iter().cycle() {
+        let len = (*len).min(total - pos);
+        let samples = sine(frequency, input_rate, pos, len);
+        let timecode = (pos as f64 * 10_000_000.0 / input_rate as f64).round() as i64;
+        res.push(resampler.apply(&buffer(input_rate, timecode, vec![samples.clone(), samples])));
+        pos += len;
+        if pos == total {
+            break;
+        }
+    }
+    res.push(resampler.flush());
+    res
+}
+
+#[test]
+fn upsampling_preserves_the_signal() {
+    let output = resample_sine(1000.0, 44100, 48000, 1.0);
+    let samples: Vec<f32> = output.iter().flat_map(|b| b.channels[0].iter().cloned()).collect();
+
+    // Every input sample produces an output, including those held back until the flush
+    assert!((samples.len() as i64 - 48000).abs() <= 1, "{}", samples.len());
+
+    // Past the silence before the stream, the output is the same sine sampled at the new rate
+    let expected = sine(1000.0, 48000, 0, samples.len());
+    for (i, (a, b)) in samples.iter().zip(expected.iter()).enumerate().skip(100).take(47000) {
+        assert!((a - b).abs() < 1e-3, "sample {}: {} != {}", i, a, b);
+    }
+    for b in &output {
+        assert_eq!(b.sample_rate, 48000);
+        assert_eq!(b.channels[0], b.channels[1]);
+    }
+}
+
+#[test]
+fn downsampling_preserves_the_signal() {
+    let output = resample_sine(1000.0, 96000, 44100, 0.5);
+    let samples: Vec<f32> = output.iter().flat_map(|b| b.channels[0].iter().cloned()).collect();
+    assert!((samples.len() as i64 - 22050).abs() <= 1, "{}", samples.len());
+
+    let expected = sine(1000.0, 44100, 0, samples.len());
+    for (i, (a, b)) in samples.iter().zip(expected.iter()).enumerate().skip(100).take(21800) {
+        assert!((a - b).abs() < 1e-3, "sample {}: {} != {}", i, a, b);
+    }
+}
+
+#[test]
+fn downsampling_removes_frequencies_above_nyquist() {
+    // 30 kHz cannot be represented at 48 kHz, and must not alias down to 18 kHz
+    let output = resample_sine(30000.0, 96000, 48000, 0.5);
+    let samples: Vec<f32> = output.iter().flat_map(|b| b.channels[0].iter().cloned()).collect();
+    let rms = (samples[200..].iter().map(|s| (s * s) as f64).sum::<f64>() / samples.len() as f64).sqrt();
+    assert!(rms < 1e-3, "{}", rms);
+}
+
+#[test]
+fn output_is_independent_of_frame_size() {
+    let input = sine(440.0, 44100, 0, 10000);
+
+    let mut whole = Resampler::new(48000);
+    let mut expected = whole.apply(&buffer(44100, 0, vec![input.clone()])).channels.remove(0);
+    expected.extend(whole.flush().channels.remove(0));
+
+    let mut split = Resampler::new(48000);
+    let mut actual = Vec::new();
+    for chunk in input.chunks(333) {
+        actual.extend(split.apply(&buffer(44100, 0, vec![chunk.to_vec()])).channels.remove(0));
+    }
+    actual.extend(split.flush().channels.remove(0));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn timecodes_match_the_first_output_sample() {
+    let output = resample_sine(1000.0, 44100, 48000, 1.0);
+    let mut position = 0;
+    for b in &output {
+        if b.channels[0].is_empty() {
+            continue;
+        }
+        let expected = (position as f64 * 10_000_000.0 / 48000.0).round() as i64;
+        let (timecode, timestamp) = (b.timecode.value().unwrap(), b.timestamp.value().unwrap());
+        assert!((timecode - expected).abs() <= 1, "{} != {}", timecode, expected);
+        assert_eq!(timestamp - timecode, 5);
+        position += b.channels[0].len();
+    }
+}
+
+#[test]
+fn matching_rates_pass_through() {
+    let input = buffer(48000, 1234, vec![vec![0.25, -0.5, 1.0]]);
+    let mut resampler = Resampler::new(48000);
+    assert_eq!(resampler.apply(&input), input);
+    assert!(resampler.flush().channels.is_empty());
+}
+
+#[test]
+fn unsupported_rates_are_dropped() {
+    let first = buffer(44100, 0, vec![sine(1000.0, 44100, 0, 4410)]);
+    let second = buffer(44100, 1_000_000, vec![sine(1000.0, 44100, 4410, 4410)]);
+    let mut reference = Resampler::new(48000);
+    reference.apply(&first);
+    let expected = reference.apply(&second);
+
+    let mut resampler = Resampler::new(48000);
+    resampler.apply(&first);
+
+    for &rate in [0, -1, 1, MIN_SAMPLE_RATE - 1, MAX_SAMPLE_RATE + 1, i32::MAX].iter() {
+        let output = resampler.apply(&buffer(rate, 1234, vec![vec![0.5; 64]; 2]));
+        assert_eq!(output.sample_rate, 48000);
+        assert_eq!(output.channels, vec![Vec::<f32>::new(); 2], "{}", rate);
+        assert_eq!(output.timecode, Timecode::Value(1234));
+    }
+
+    // The stream around them carries on as if they were never seen
+    assert_eq!(resampler.apply(&second), expected);
+}
+
+#[test]
+#[should_panic(expected = "invalid output rate 1")]
+fn unsupported_output_rate_panics() {
+    Resampler::new(1);
+}
+
+#[test]
+fn format_changes_reset_the_resampler() {
+    let mut resampler = Resampler::new(48000);
+    resampler.apply(&buffer(44100, 0, vec![sine(1000.0, 44100, 0, 4410)]));
+
+    let output = resampler.apply(&buffer(96000, 777, vec![vec![0.0; 9600]; 2]));
+    assert_eq!(output.channels.len(), 2);
+    // A new stream starts at the given timecode
+    assert_eq!(output.timecode, Timecode::Value(777));
+}
+
+#[test]
+fn remix_layouts() {
+    let m = ChannelMatrix::remix(1, 2);
+    assert_eq!((m.gain(0, 0), m.gain(1, 0)), (1.0, 1.0));
+
+    let m = ChannelMatrix::remix(6, 2);
+    let h = FRAC_1_SQRT_2 as f32;
+    assert_eq!(
+        (0..6).map(|i| m.gain(0, i)).collect::<Vec<_>>(),
+        [1.0, 0.0, h, 0.0, h, 0.0]
+    );
+    assert_eq!(
+        (0..6).map(|i| m.gain(1, i)).collect::<Vec<_>>(),
+        [0.0, 1.0, h, 0.0, 0.0, h]
+    );
+
+    let m = ChannelMatrix::remix(8, 2);
+    assert_eq!(m.gain(0, 6), h);
+    assert_eq!(m.gain(1, 7), h);
+    assert_eq!(m.gain(0, 3), 0.0);
+
+    let m = ChannelMatrix::remix(2, 1);
+    assert_eq!((m.gain(0, 0), m.gain(0, 1)), (0.5, 0.5));
+
+    let m = ChannelMatrix::remix(2, 4);
+    assert_eq!(m, ChannelMatrix::route(2, &[Some(0), Some(1), None, None]));
+    let m = ChannelMatrix::remix(4, 2);
+    assert_eq!(m, ChannelMatrix::route(4, &[Some(0), Some(1)]));
+    assert_eq!(ChannelMatrix::remix(3, 3), ChannelMatrix::identity(3));
+}
+
+#[test]
+fn matrix_mixes_channels() {
+    let mut m = ChannelMatrix::route(3, &[Some(2), None, Some(0)]);
+    m.set_gain(1, 0, 0.5);
+    m.set_gain(1, 1, -1.0);
+    assert_eq!((m.input_count(), m.output_count()), (3, 3));
+
+    let input = buffer(48000, 42, vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![5.0, 6.0]]);
+    let output = m.apply(&input).unwrap();
+    assert_eq!(output.channels, [[5.0, 6.0], [-2.5, -3.0], [1.0, 2.0]]);
+    assert_eq!(
+        (output.sample_rate, output.timecode, output.timestamp),
+        (48000, Timecode::Value(42), Timestamp::Value(47))
+    );
+
+    // The wrong number of channels
+    assert_eq!(m.apply(&buffer(48000, 0, vec![vec![0.0]; 2])), None);
+}
+
+#[test]
+#[should_panic(expected = "input 3 out of range")]
+fn matrix_rejects_unknown_inputs() {
+    ChannelMatrix::route(3, &[Some(3)]);
+}
+
+#[test]
+fn buffers_become_send_frames() {
+    let stereo = buffer(48000, 0, vec![vec![0.0; 16]; 2]);
+    assert_eq!(stereo.sample_count(), 16);
+    assert!(stereo.into_send_frame_builder().build().is_ok());
+
+    let ragged = buffer(48000, 0, vec![vec![0.0; 16], vec![0.0; 15]]);
+    assert!(ragged.into_send_frame_builder().build().is_err());
+}
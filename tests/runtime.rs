@@ -0,0 +1,52 @@
+#![cfg(feature = "dynamic-link")]
+
+mod common;
+
+// The runtime is global to the process, so everything is checked in a single test to avoid interference
+#[test]
+fn runtime_is_shared_and_destroyed_last() {
+    assert_eq!(common::stub_initialized(), 0);
+
+    let first = common::load();
+    let second = common::load();
+    assert_eq!(common::stub_initialized(), 1);
+
+    let finder = second.create_find_instance(true).expect("create finder");
+    drop(first);
+    drop(second);
+    assert_eq!(common::stub_initialized(), 1);
+
+    // A new load while the finder is alive reuses the same runtime
+    let third = common::load();
+    assert_eq!(common::stub_initialized(), 1);
+    drop(third);
+
+    assert_eq!(finder.get_current_sources().len(), 1);
+    drop(finder);
+    assert_eq!(common::stub_initialized(), 0);
+
+    // Loading after everything has been dropped initialises it again
+    let instance = common::load();
+    assert_eq!(common::stub_initialized(), 1);
+    drop(instance);
+    assert_eq!(common::stub_initialized(), 0);
+
+    // Racing loads and drops never leave the runtime initialised more than once, or destroyed while in use
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            std::thread::spawn(|| {
+                for _ in 0..100 {
+                    let instance = common::load();
+                    let finder = instance.create_find_instance(true).expect("create finder");
+                    drop(instance);
+                    assert_eq!(common::stub_initialized(), 1);
+                    assert_eq!(finder.get_current_sources().len(), 1);
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(common::stub_initialized(), 0);
+}
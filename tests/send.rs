@@ -0,0 +1,223 @@
+#![cfg(feature = "dynamic-link")]
+
+mod common;
+
+use ndi_sdk::send::{
+    create_ndi_send_audio_frame, create_ndi_send_video_frame, FourCCType, FrameBuildError, FrameFormatType,
+};
+use ndi_sdk::time::FrameRate;
+
+#[test]
+fn borrowed_video_is_flushed_before_returning() {
+    let instance = common::load();
+    let mut sender = instance
+        .create_send_instance("borrowed".to_string(), false, false)
+        .expect("create sender");
+
+    let first = vec![1u8; 16 * 8 * 4];
+    let second = vec![2u8; 16 * 8 * 4];
+    sender.send_video_async_borrowed(|video| {
+        let frame = create_ndi_send_video_frame(16, 8, FrameFormatType::Progressive)
+            .with_framerate(FrameRate::FPS_30)
+            .build_borrowed(&first, 16 * 4, FourCCType::BGRA)
+            .expect("build frame");
+        video.send(frame);
+
+        let stats = common::sender_stats("borrowed");
+        assert!(stats.video_in_flight);
+        assert_eq!(stats.video_frames, 0);
+
+        let frame = create_ndi_send_video_frame(16, 8, FrameFormatType::Progressive)
+            .with_framerate(FrameRate::FPS_30)
+            .build_borrowed(&second, 16 * 4, FourCCType::BGRA)
+            .expect("build frame");
+        video.send(frame);
+
+        // Sending the second frame releases the first
+        let stats = common::sender_stats("borrowed");
+        assert_eq!(stats.video_frames, 1);
+        assert_eq!(stats.video_checksum, first.len() as u64);
+    });
+
+    let stats = common::sender_stats("borrowed");
+    assert!(!stats.video_in_flight);
+    assert_eq!(stats.video_frames, 2);
+    assert_eq!(stats.video_checksum, 2 * second.len() as u64);
+}
+
+#[test]
+fn borrowed_video_rejects_short_data() {
+    let data = vec![0u8; 16 * 8 * 4 - 1];
+    let frame = create_ndi_send_video_frame(16, 8, FrameFormatType::Progressive)
+        .with_framerate(FrameRate::FPS_30)
+        .build_borrowed(&data, 16 * 4, FourCCType::BGRA);
+    assert!(frame.is_err());
+}
+
+#[test]
+fn pooled_buffers_are_reused_after_release() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("pooled".to_string(), false, false)
+        .expect("create sender");
+    let pool = sender
+        .create_video_frame_pool(16, 8, 16 * 4, FourCCType::BGRA)
+        .expect("create pool");
+
+    for i in 0..10u8 {
+        let mut buffer = pool.acquire();
+        assert_eq!(buffer.len(), 16 * 8 * 4);
+        for b in buffer.iter_mut() {
+            *b = i;
+        }
+
+        let frame = create_ndi_send_video_frame(16, 8, FrameFormatType::Progressive)
+            .with_framerate(FrameRate::FPS_30)
+            .with_pooled_data(buffer)
+            .build()
+            .expect("build frame");
+        sender.send_video_async(frame);
+    }
+
+    // One buffer is in flight while the next is filled, so two are enough
+    assert_eq!(pool.allocated(), 2);
+    assert_eq!(pool.available(), 1);
+
+    let stats = common::sender_stats("pooled");
+    assert_eq!(stats.video_frames, 9);
+    assert_eq!(stats.video_checksum, 8 * 16 * 8 * 4);
+
+    sender.send_video_flush();
+    assert_eq!(pool.available(), 2);
+    let stats = common::sender_stats("pooled");
+    assert_eq!(stats.video_checksum, 9 * 16 * 8 * 4);
+}
+
+#[test]
+fn pooled_buffer_must_fit_frame() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("pooled-mismatch".to_string(), false, false)
+        .expect("create sender");
+    let pool = sender
+        .create_video_frame_pool(16, 8, 16 * 4, FourCCType::BGRA)
+        .expect("create pool");
+
+    for (width, height) in [(16, 16), (16, 4)] {
+        let frame = create_ndi_send_video_frame(width, height, FrameFormatType::Progressive)
+            .with_framerate(FrameRate::FPS_30)
+            .with_pooled_data(pool.acquire())
+            .build();
+        assert_eq!(
+            frame.err(),
+            Some(FrameBuildError::MismatchedPoolDimensions {
+                width,
+                height,
+                pool_width: 16,
+                pool_height: 8
+            })
+        );
+    }
+
+    // The buffer is returned to the pool when the frame fails to build
+    assert_eq!(pool.available(), 1);
+    assert_eq!(pool.allocated(), 1);
+}
+
+#[test]
+fn pooled_buffer_outliving_its_pool_is_rejected() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("pooled-dropped".to_string(), false, false)
+        .expect("create sender");
+    let pool = sender
+        .create_video_frame_pool(16, 8, 16 * 4, FourCCType::BGRA)
+        .expect("create pool");
+    let buffer = pool.acquire();
+    drop(pool);
+
+    let frame = create_ndi_send_video_frame(16, 8, FrameFormatType::Progressive)
+        .with_framerate(FrameRate::FPS_30)
+        .with_pooled_data(buffer)
+        .build();
+    assert_eq!(frame.err(), Some(FrameBuildError::PoolDropped));
+}
+
+#[test]
+fn synchronous_sends_release_pooled_buffers() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("pooled-sync".to_string(), false, false)
+        .expect("create sender");
+    let pool = sender
+        .create_video_frame_pool(16, 8, 16 * 4, FourCCType::BGRA)
+        .expect("create pool");
+
+    for _ in 0..10 {
+        let frame = create_ndi_send_video_frame(16, 8, FrameFormatType::Progressive)
+            .with_framerate(FrameRate::FPS_30)
+            .with_pooled_data(pool.acquire())
+            .build()
+            .expect("build frame");
+        sender.send_video(frame);
+        assert_eq!(pool.available(), 1);
+    }
+    assert_eq!(pool.allocated(), 1);
+    assert_eq!(common::sender_stats("pooled-sync").video_frames, 10);
+}
+
+#[test]
+fn video_metadata_is_valid_until_sent() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("video-metadata".to_string(), false, false)
+        .expect("create sender");
+
+    let frame = create_ndi_send_video_frame(4, 4, FrameFormatType::Progressive)
+        .with_framerate(FrameRate::FPS_30)
+        .with_data(vec![0; 4 * 4 * 4], 4 * 4, FourCCType::BGRA)
+        .with_metadata("<video frame=\"1\"/>".to_string())
+        .build()
+        .expect("build frame");
+
+    // Churn the allocator, so that freed metadata would likely be overwritten
+    let junk: Vec<_> = (0..64).map(|i| vec![i as u8; 32]).collect();
+    drop(junk);
+
+    // The stub only reads an async frame once it is released, so this checks the metadata outlives the send call
+    sender.send_video_async(frame);
+    sender.send_video_flush();
+    assert_eq!(
+        common::sender_stats("video-metadata").video_metadata,
+        "<video frame=\"1\"/>"
+    );
+}
+
+#[test]
+fn audio_metadata_is_sent() {
+    let instance = common::load();
+    let sender = instance
+        .create_send_instance("audio-metadata".to_string(), false, false)
+        .expect("create sender");
+
+    let frame = create_ndi_send_audio_frame(2, 48000)
+        .with_data(vec![0.25; 2 * 16], 16)
+        .with_metadata("<audio frame=\"1\"/>".to_string())
+        .build()
+        .expect("build frame");
+    sender.send_audio(frame);
+
+    let stats = common::sender_stats("audio-metadata");
+    assert_eq!(stats.audio_frames, 1);
+    assert_eq!(stats.audio_sum, 0.25 * 2.0 * 16.0);
+    assert_eq!(stats.audio_metadata, "<audio frame=\"1\"/>");
+}
+
+#[test]
+fn metadata_with_nul_is_rejected() {
+    let res = create_ndi_send_audio_frame(2, 48000)
+        .with_data(vec![0.0; 2 * 16], 16)
+        .with_metadata("bad\0metadata".to_string())
+        .build();
+    assert!(matches!(res, Err(FrameBuildError::InvalidMetadata)));
+}
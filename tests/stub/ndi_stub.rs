@@ -0,0 +1,627 @@
+// A stand-in for the NDI runtime, used by the tests when built with the 'dynamic-link' feature.
+// It is compiled into a shared library by tests/common/mod.rs, and exposes the same NDIlib_v3_load entry point as the
+// real runtime, along with a few extra functions that let the tests inspect what the library was asked to do.
+// Video and audio sent by a stub sender are looped back to any stub receiver connected to a source with the sender's
+// name, with padding added between the audio channels.
+
+#![allow(clippy::missing_safety_doc)]
+
+#[allow(non_snake_case, non_camel_case_types, non_upper_case_globals, dead_code, clippy::all)]
+mod sdk {
+    include!("../../src/sdk.rs");
+}
+
+use std::collections::{HashMap, VecDeque};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr::null;
+use std::slice;
+use std::sync::atomic::{AtomicBool, AtomicIsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+static INITIALIZED: AtomicIsize = AtomicIsize::new(0);
+
+unsafe extern "C" fn initialize() -> bool {
+    INITIALIZED.fetch_add(1, Ordering::SeqCst);
+    true
+}
+unsafe extern "C" fn destroy() {
+    INITIALIZED.fetch_sub(1, Ordering::SeqCst);
+}
+unsafe extern "C" fn version() -> *const c_char {
+    b"NDI stub\0".as_ptr() as *const c_char
+}
+unsafe extern "C" fn is_supported_cpu() -> bool {
+    true
+}
+
+struct Finder {
+    sources: [sdk::NDIlib_source_t; 1],
+}
+
+unsafe extern "C" fn find_create(_settings: *const sdk::NDIlib_find_create_t) -> sdk::NDIlib_find_instance_t {
+    let finder = Box::new(Finder {
+        sources: [sdk::NDIlib_source_t {
+            p_ndi_name: b"STUB (Source 1)\0".as_ptr() as *const c_char,
+            __bindgen_anon_1: sdk::NDIlib_source_t__bindgen_ty_1 { p_url_address: null() },
+        }],
+    });
+    Box::into_raw(finder) as sdk::NDIlib_find_instance_t
+}
+unsafe extern "C" fn find_destroy(instance: sdk::NDIlib_find_instance_t) {
+    drop(Box::from_raw(instance as *mut Finder));
+}
+unsafe extern "C" fn find_wait_for_sources(_instance: sdk::NDIlib_find_instance_t, _timeout: u32) -> bool {
+    true
+}
+unsafe extern "C" fn find_get_current_sources(
+    instance: sdk::NDIlib_find_instance_t,
+    count: *mut u32,
+) -> *const sdk::NDIlib_source_t {
+    let finder = &*(instance as *const Finder);
+    *count = finder.sources.len() as u32;
+    finder.sources.as_ptr()
+}
+
+/// What a sender has been asked to send. Shared with tests/common/mod.rs
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SenderStats {
+    /// Video frames whose data has been read
+    pub video_frames: u64,
+    /// Whether an asynchronous video frame is still being read
+    pub video_in_flight: bool,
+    /// Sum of the bytes of the last video frame read
+    pub video_checksum: u64,
+    /// Metadata attached to the last video frame read
+    pub video_metadata: [c_char; 256],
+    /// Audio frames whose data has been read
+    pub audio_frames: u64,
+    /// Sum of the samples of the last audio frame read
+    pub audio_sum: f64,
+    /// Metadata attached to the last audio frame read
+    pub audio_metadata: [c_char; 256],
+    /// Video frames submitted while another call to send video was still in progress
+    pub video_overlaps: u64,
+}
+
+static SENDERS: Mutex<Option<HashMap<String, SenderStats>>> = Mutex::new(None);
+
+fn with_sender_stats<F: FnOnce(&mut SenderStats)>(name: &str, f: F) {
+    let mut senders = SENDERS.lock().unwrap();
+    let stats = senders
+        .get_or_insert_with(HashMap::new)
+        .entry(name.to_string())
+        // All zeroes is a valid, empty, set of stats
+        .or_insert_with(|| unsafe { std::mem::zeroed() });
+    f(stats)
+}
+
+unsafe fn copy_metadata(dest: &mut [c_char; 256], metadata: *const c_char) {
+    *dest = [0; 256];
+    if !metadata.is_null() {
+        let bytes = CStr::from_ptr(metadata).to_bytes();
+        for (d, s) in dest.iter_mut().zip(bytes.iter().take(255)) {
+            *d = *s as c_char;
+        }
+    }
+}
+
+struct Sender {
+    name: String,
+    in_flight_video: Mutex<Option<sdk::NDIlib_video_frame_v2_t>>,
+    /// Whether a call to send video is in progress
+    sending_video: AtomicBool,
+}
+impl Sender {
+    /// Note the start of a call to send video, recording it if it overlaps another
+    fn start_video(&self) {
+        if self.sending_video.swap(true, Ordering::SeqCst) {
+            with_sender_stats(&self.name, |stats| stats.video_overlaps += 1);
+        }
+        // Give any other thread sending video a chance to run into this one
+        std::thread::yield_now();
+    }
+    fn end_video(&self) {
+        self.sending_video.store(false, Ordering::SeqCst);
+    }
+    /// Read a frame, as the SDK would when encoding it
+    unsafe fn read_video(&self, frame: &sdk::NDIlib_video_frame_v2_t) {
+        let data = slice::from_raw_parts(frame.p_data, (frame.line_stride_in_bytes * frame.yres) as usize);
+        let checksum = data.iter().map(|b| *b as u64).sum();
+        queue_video(&self.name, frame);
+        with_sender_stats(&self.name, |stats| {
+            stats.video_frames += 1;
+            stats.video_checksum = checksum;
+            copy_metadata(&mut stats.video_metadata, frame.p_metadata);
+        });
+    }
+    unsafe fn release_video(&self) {
+        let frame = self.in_flight_video.lock().unwrap().take();
+        if let Some(frame) = frame {
+            self.read_video(&frame);
+        }
+        with_sender_stats(&self.name, |stats| stats.video_in_flight = false);
+    }
+}
+
+unsafe extern "C" fn send_create(settings: *const sdk::NDIlib_send_create_t) -> sdk::NDIlib_send_instance_t {
+    let name = CStr::from_ptr((*settings).p_ndi_name).to_string_lossy().into_owned();
+    with_sender_stats(&name, |_| {});
+    Box::into_raw(Box::new(Sender {
+        name,
+        in_flight_video: Mutex::new(None),
+        sending_video: AtomicBool::new(false),
+    })) as sdk::NDIlib_send_instance_t
+}
+unsafe extern "C" fn send_destroy(instance: sdk::NDIlib_send_instance_t) {
+    let sender = Box::from_raw(instance as *mut Sender);
+    sender.release_video();
+}
+unsafe extern "C" fn send_video(instance: sdk::NDIlib_send_instance_t, frame: *const sdk::NDIlib_video_frame_v2_t) {
+    let sender = &*(instance as *const Sender);
+    sender.start_video();
+    sender.release_video();
+    sender.read_video(&*frame);
+    sender.end_video();
+}
+unsafe extern "C" fn send_video_async(
+    instance: sdk::NDIlib_send_instance_t,
+    frame: *const sdk::NDIlib_video_frame_v2_t,
+) {
+    let sender = &*(instance as *const Sender);
+    sender.start_video();
+    // The previous frame is finished with once the next one is submitted
+    sender.release_video();
+    if let Some(frame) = frame.as_ref() {
+        *sender.in_flight_video.lock().unwrap() = Some(*frame);
+        with_sender_stats(&sender.name, |stats| stats.video_in_flight = true);
+    }
+    sender.end_video();
+}
+
+unsafe extern "C" fn send_audio(instance: sdk::NDIlib_send_instance_t, frame: *const sdk::NDIlib_audio_frame_v2_t) {
+    let sender = &*(instance as *const Sender);
+    let frame = &*frame;
+    queue_audio(&sender.name, frame);
+    let stride = frame.channel_stride_in_bytes as usize / 4;
+    let mut sum = 0.0;
+    for channel in 0..frame.no_channels as usize {
+        let data = slice::from_raw_parts(frame.p_data.add(channel * stride), frame.no_samples as usize);
+        sum += data.iter().map(|s| *s as f64).sum::<f64>();
+    }
+    with_sender_stats(&sender.name, |stats| {
+        stats.audio_frames += 1;
+        stats.audio_sum = sum;
+        copy_metadata(&mut stats.audio_metadata, frame.p_metadata);
+    });
+}
+
+/// An audio frame sent by a stub sender, waiting to be captured by a stub receiver connected to it
+struct QueuedAudio {
+    frame: sdk::NDIlib_audio_frame_v2_t,
+    data: Vec<f32>,
+    metadata: Option<CString>,
+}
+/// A video frame sent by a stub sender, waiting to be captured by a stub receiver connected to it
+struct QueuedVideo {
+    frame: sdk::NDIlib_video_frame_v2_t,
+    data: Vec<u8>,
+    metadata: Option<CString>,
+}
+// The pointers in the frames are replaced before they are handed to a receiver
+unsafe impl Send for QueuedAudio {}
+unsafe impl Send for QueuedVideo {}
+
+/// The frames sent by one sender which have not been captured
+#[derive(Default)]
+struct Queue {
+    audio: VecDeque<QueuedAudio>,
+    video: VecDeque<QueuedVideo>,
+    /// Metadata and its timecode, added by ndi_stub_queue_metadata
+    metadata: VecDeque<(CString, i64)>,
+    /// Status changes added by ndi_stub_queue_status_change, which are reported before any frame
+    status_changes: usize,
+}
+
+static QUEUES: Mutex<Option<HashMap<String, Queue>>> = Mutex::new(None);
+/// Frames which have been captured, but not yet freed
+static OUTSTANDING: AtomicIsize = AtomicIsize::new(0);
+/// Receivers which were destroyed before every frame captured from them was freed
+static DESTROYED_EARLY: AtomicIsize = AtomicIsize::new(0);
+
+/// Receivers pad each channel to a multiple of this many samples, as the SDK may align them
+const RECEIVE_ALIGNMENT: usize = 16;
+
+fn with_queue<T, F: FnOnce(&mut Queue) -> T>(name: &str, f: F) -> T {
+    let mut queues = QUEUES.lock().unwrap();
+    f(queues.get_or_insert_with(HashMap::new).entry(name.to_string()).or_default())
+}
+
+unsafe fn copy_metadata_string(metadata: *const c_char) -> Option<CString> {
+    metadata.as_ref().map(|m| CStr::from_ptr(m).to_owned())
+}
+
+// The 16 bit formats, which the bindings predate
+const FOURCC_P216: u32 = u32::from_le_bytes(*b"P216");
+const FOURCC_PA16: u32 = u32::from_le_bytes(*b"PA16");
+
+/// The size of a video frame, including every plane
+fn video_size(frame: &sdk::NDIlib_video_frame_v2_t) -> usize {
+    let (width, height, stride) = (frame.xres as usize, frame.yres as usize, frame.line_stride_in_bytes as usize);
+    let chroma_height = height.div_ceil(2);
+    match frame.FourCC {
+        sdk::NDIlib_FourCC_type_UYVA => stride * height + width * height,
+        sdk::NDIlib_FourCC_type_I420 | sdk::NDIlib_FourCC_type_YV12 => {
+            stride * height + 2 * (stride / 2) * chroma_height
+        }
+        sdk::NDIlib_FourCC_type_NV12 => stride * height + stride * chroma_height,
+        FOURCC_P216 => stride * height * 2,
+        FOURCC_PA16 => stride * height * 3,
+        _ => stride * height,
+    }
+}
+
+unsafe fn queue_video(name: &str, frame: &sdk::NDIlib_video_frame_v2_t) {
+    let queued = QueuedVideo {
+        frame: *frame,
+        data: slice::from_raw_parts(frame.p_data, video_size(frame)).to_vec(),
+        metadata: copy_metadata_string(frame.p_metadata),
+    };
+    with_queue(name, |q| q.video.push_back(queued));
+}
+
+unsafe fn queue_audio(name: &str, frame: &sdk::NDIlib_audio_frame_v2_t) {
+    let samples = frame.no_samples as usize;
+    let send_stride = frame.channel_stride_in_bytes as usize / 4;
+    let stride = samples.next_multiple_of(RECEIVE_ALIGNMENT);
+
+    // Fill the padding with a value which stands out if it is ever read as audio
+    let mut data = vec![f32::NAN; stride * frame.no_channels as usize];
+    for (channel, dest) in data.chunks_exact_mut(stride.max(1)).enumerate() {
+        let src = slice::from_raw_parts(frame.p_data.add(channel * send_stride), samples);
+        dest[..samples].copy_from_slice(src);
+    }
+
+    let queued = QueuedAudio {
+        frame: sdk::NDIlib_audio_frame_v2_t {
+            channel_stride_in_bytes: (stride * 4) as i32,
+            ..*frame
+        },
+        data,
+        metadata: copy_metadata_string(frame.p_metadata),
+    };
+    with_queue(name, |q| q.audio.push_back(queued));
+}
+
+struct Receiver {
+    source: Option<String>,
+    /// Frames captured from this receiver which have not been freed
+    outstanding: AtomicIsize,
+}
+impl Receiver {
+    fn captured(&self) {
+        self.outstanding.fetch_add(1, Ordering::SeqCst);
+        OUTSTANDING.fetch_add(1, Ordering::SeqCst);
+    }
+    fn freed(&self) {
+        self.outstanding.fetch_sub(1, Ordering::SeqCst);
+        OUTSTANDING.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+unsafe fn source_name(source: *const sdk::NDIlib_source_t) -> Option<String> {
+    let source = source.as_ref()?;
+    source
+        .p_ndi_name
+        .as_ref()
+        .map(|name| CStr::from_ptr(name).to_string_lossy().into_owned())
+}
+
+unsafe extern "C" fn recv_create(settings: *const sdk::NDIlib_recv_create_v3_t) -> sdk::NDIlib_recv_instance_t {
+    let source = source_name(&(*settings).source_to_connect_to);
+    Box::into_raw(Box::new(Receiver {
+        source,
+        outstanding: AtomicIsize::new(0),
+    })) as sdk::NDIlib_recv_instance_t
+}
+unsafe extern "C" fn recv_destroy(instance: sdk::NDIlib_recv_instance_t) {
+    let receiver = Box::from_raw(instance as *mut Receiver);
+    if receiver.outstanding.load(Ordering::SeqCst) != 0 {
+        DESTROYED_EARLY.fetch_add(1, Ordering::SeqCst);
+    }
+}
+unsafe extern "C" fn recv_connect(instance: sdk::NDIlib_recv_instance_t, source: *const sdk::NDIlib_source_t) {
+    let receiver = &mut *(instance as *mut Receiver);
+    receiver.source = source_name(source);
+}
+/// Hand the data of a frame over to the caller. Frames without any data are given a null pointer, as the SDK does
+fn into_raw_data<T>(data: Vec<T>) -> *mut T {
+    if data.is_empty() {
+        std::ptr::null_mut()
+    } else {
+        Box::into_raw(data.into_boxed_slice()) as *mut T
+    }
+}
+unsafe fn free_raw_data<T>(p_data: *mut T, len: usize) {
+    if !p_data.is_null() {
+        drop(Box::from_raw(slice::from_raw_parts_mut(p_data, len)));
+    }
+}
+
+unsafe extern "C" fn recv_capture(
+    instance: sdk::NDIlib_recv_instance_t,
+    video: *mut sdk::NDIlib_video_frame_v2_t,
+    audio: *mut sdk::NDIlib_audio_frame_v2_t,
+    metadata: *mut sdk::NDIlib_metadata_frame_t,
+    _timeout: u32,
+) -> sdk::NDIlib_frame_type_e {
+    let receiver = &*(instance as *const Receiver);
+    let source = match &receiver.source {
+        Some(source) => source,
+        None => return sdk::NDIlib_frame_type_none,
+    };
+    let status_changed = with_queue(source, |q| {
+        let changed = q.status_changes > 0;
+        q.status_changes = q.status_changes.saturating_sub(1);
+        changed
+    });
+    if status_changed {
+        return sdk::NDIlib_frame_type_status_change;
+    }
+    // Handed over to the caller until it frees the frame
+    if let Some(video) = video.as_mut() {
+        if let Some(queued) = with_queue(source, |q| q.video.pop_front()) {
+            *video = queued.frame;
+            video.p_data = into_raw_data(queued.data);
+            video.p_metadata = queued.metadata.map_or(null(), |m| m.into_raw() as *const c_char);
+            receiver.captured();
+            return sdk::NDIlib_frame_type_video;
+        }
+    }
+    if let Some(audio) = audio.as_mut() {
+        if let Some(queued) = with_queue(source, |q| q.audio.pop_front()) {
+            *audio = queued.frame;
+            audio.p_data = into_raw_data(queued.data);
+            audio.p_metadata = queued.metadata.map_or(null(), |m| m.into_raw() as *const c_char);
+            receiver.captured();
+            return sdk::NDIlib_frame_type_audio;
+        }
+    }
+    if let Some(metadata) = metadata.as_mut() {
+        if let Some((data, timecode)) = with_queue(source, |q| q.metadata.pop_front()) {
+            metadata.length = data.as_bytes().len() as i32;
+            metadata.timecode = timecode;
+            metadata.p_data = data.into_raw();
+            receiver.captured();
+            return sdk::NDIlib_frame_type_metadata;
+        }
+    }
+    sdk::NDIlib_frame_type_none
+}
+unsafe extern "C" fn recv_free_metadata(
+    instance: sdk::NDIlib_recv_instance_t,
+    metadata: *const sdk::NDIlib_metadata_frame_t,
+) {
+    drop(CString::from_raw((*metadata).p_data));
+    (*(instance as *const Receiver)).freed();
+}
+unsafe extern "C" fn recv_free_video(instance: sdk::NDIlib_recv_instance_t, video: *const sdk::NDIlib_video_frame_v2_t) {
+    let video = &*video;
+    free_raw_data(video.p_data, video_size(video));
+    if !video.p_metadata.is_null() {
+        drop(CString::from_raw(video.p_metadata as *mut c_char));
+    }
+    (*(instance as *const Receiver)).freed();
+}
+unsafe extern "C" fn recv_free_audio(instance: sdk::NDIlib_recv_instance_t, audio: *const sdk::NDIlib_audio_frame_v2_t) {
+    let audio = &*audio;
+    let len = (audio.no_channels * audio.channel_stride_in_bytes / 4) as usize;
+    free_raw_data(audio.p_data, len);
+    if !audio.p_metadata.is_null() {
+        drop(CString::from_raw(audio.p_metadata as *mut c_char));
+    }
+    (*(instance as *const Receiver)).freed();
+}
+
+/// Whether the table includes the interleaved audio utilities. Set by ndi_stub_enable_util, before the table is loaded
+static UTIL_ENABLED: AtomicBool = AtomicBool::new(false);
+/// How many times the interleaved audio utilities have been called
+static UTIL_CALLS: AtomicIsize = AtomicIsize::new(0);
+
+/// The value of a full scale integer sample, for a full scale `reference_level` dB above the floating point reference
+fn util_scale(full_scale: f64, reference_level: i32) -> f64 {
+    full_scale / 10f64.powf(reference_level as f64 / 20.0)
+}
+
+unsafe fn util_to_interleaved<T, F: Fn(f32) -> T>(src: &sdk::NDIlib_audio_frame_v2_t, dst: *mut T, convert: F) {
+    UTIL_CALLS.fetch_add(1, Ordering::SeqCst);
+    let (channels, samples) = (src.no_channels as usize, src.no_samples as usize);
+    let stride = src.channel_stride_in_bytes as usize / 4;
+    let dst = slice::from_raw_parts_mut(dst, channels * samples);
+    for channel in 0..channels {
+        let data = slice::from_raw_parts(src.p_data.add(channel * stride), samples);
+        for (sample, s) in data.iter().enumerate() {
+            dst[sample * channels + channel] = convert(*s);
+        }
+    }
+}
+unsafe extern "C" fn util_to_interleaved_16s(
+    src: *const sdk::NDIlib_audio_frame_v2_t,
+    dst: *mut sdk::NDIlib_audio_frame_interleaved_16s_t,
+) {
+    let scale = util_scale(i16::MAX as f64, (*dst).reference_level);
+    util_to_interleaved(&*src, (*dst).p_data, |s| {
+        (s as f64 * scale).round().clamp(i16::MIN as f64, i16::MAX as f64) as i16
+    });
+}
+unsafe extern "C" fn util_to_interleaved_32s(
+    src: *const sdk::NDIlib_audio_frame_v2_t,
+    dst: *mut sdk::NDIlib_audio_frame_interleaved_32s_t,
+) {
+    let scale = util_scale(i32::MAX as f64, (*dst).reference_level);
+    util_to_interleaved(&*src, (*dst).p_data, |s| {
+        (s as f64 * scale).round().clamp(i32::MIN as f64, i32::MAX as f64) as i32
+    });
+}
+unsafe extern "C" fn util_to_interleaved_32f(
+    src: *const sdk::NDIlib_audio_frame_v2_t,
+    dst: *mut sdk::NDIlib_audio_frame_interleaved_32f_t,
+) {
+    util_to_interleaved(&*src, (*dst).p_data, |s| s);
+}
+
+/// Split interleaved audio into planes, and send it as if it was a planar frame
+unsafe fn util_send_interleaved<T: Copy, F: Fn(T) -> f32>(
+    instance: sdk::NDIlib_send_instance_t,
+    sample_rate: i32,
+    channel_count: i32,
+    sample_count: i32,
+    timecode: i64,
+    data: *const T,
+    convert: F,
+) {
+    UTIL_CALLS.fetch_add(1, Ordering::SeqCst);
+    let (channels, samples) = (channel_count as usize, sample_count as usize);
+    let mut planar = vec![0.0; channels * samples];
+    for (i, s) in slice::from_raw_parts(data, channels * samples).iter().enumerate() {
+        planar[i % channels * samples + i / channels] = convert(*s);
+    }
+    let frame = sdk::NDIlib_audio_frame_v2_t {
+        sample_rate,
+        no_channels: channel_count,
+        no_samples: sample_count,
+        timecode,
+        p_data: planar.as_mut_ptr(),
+        channel_stride_in_bytes: sample_count * 4,
+        p_metadata: null(),
+        timestamp: 0,
+    };
+    send_audio(instance, &frame);
+}
+unsafe extern "C" fn util_send_interleaved_16s(
+    instance: sdk::NDIlib_send_instance_t,
+    frame: *const sdk::NDIlib_audio_frame_interleaved_16s_t,
+) {
+    let f = &*frame;
+    let scale = util_scale(i16::MAX as f64, f.reference_level);
+    util_send_interleaved(instance, f.sample_rate, f.no_channels, f.no_samples, f.timecode, f.p_data, |s| {
+        (s as f64 / scale) as f32
+    });
+}
+unsafe extern "C" fn util_send_interleaved_32s(
+    instance: sdk::NDIlib_send_instance_t,
+    frame: *const sdk::NDIlib_audio_frame_interleaved_32s_t,
+) {
+    let f = &*frame;
+    let scale = util_scale(i32::MAX as f64, f.reference_level);
+    util_send_interleaved(instance, f.sample_rate, f.no_channels, f.no_samples, f.timecode, f.p_data, |s| {
+        (s as f64 / scale) as f32
+    });
+}
+unsafe extern "C" fn util_send_interleaved_32f(
+    instance: sdk::NDIlib_send_instance_t,
+    frame: *const sdk::NDIlib_audio_frame_interleaved_32f_t,
+) {
+    let f = &*frame;
+    util_send_interleaved(instance, f.sample_rate, f.no_channels, f.no_samples, f.timecode, f.p_data, |s| s);
+}
+
+fn table() -> sdk::NDIlib_v3 {
+    // Every function the stub does not implement is left as None
+    let mut table: sdk::NDIlib_v3 = unsafe { std::mem::zeroed() };
+    table.NDIlib_initialize = Some(initialize);
+    table.NDIlib_destroy = Some(destroy);
+    table.NDIlib_version = Some(version);
+    table.NDIlib_is_supported_CPU = Some(is_supported_cpu);
+    table.NDIlib_find_create_v2 = Some(find_create);
+    table.NDIlib_find_destroy = Some(find_destroy);
+    table.NDIlib_find_wait_for_sources = Some(find_wait_for_sources);
+    table.NDIlib_find_get_current_sources = Some(find_get_current_sources);
+    table.NDIlib_send_create = Some(send_create);
+    table.NDIlib_send_destroy = Some(send_destroy);
+    table.NDIlib_send_send_video_v2 = Some(send_video);
+    table.NDIlib_send_send_video_async_v2 = Some(send_video_async);
+    table.NDIlib_send_send_audio_v2 = Some(send_audio);
+    table.NDIlib_recv_create_v3 = Some(recv_create);
+    table.NDIlib_recv_destroy = Some(recv_destroy);
+    table.NDIlib_recv_connect = Some(recv_connect);
+    table.NDIlib_recv_capture_v2 = Some(recv_capture);
+    table.NDIlib_recv_free_video_v2 = Some(recv_free_video);
+    table.NDIlib_recv_free_audio_v2 = Some(recv_free_audio);
+    table.NDIlib_recv_free_metadata = Some(recv_free_metadata);
+    if UTIL_ENABLED.load(Ordering::SeqCst) {
+        table.NDIlib_util_audio_to_interleaved_16s_v2 = Some(util_to_interleaved_16s);
+        table.NDIlib_util_audio_to_interleaved_32s_v2 = Some(util_to_interleaved_32s);
+        table.NDIlib_util_audio_to_interleaved_32f_v2 = Some(util_to_interleaved_32f);
+        table.NDIlib_util_send_send_audio_interleaved_16s = Some(util_send_interleaved_16s);
+        table.NDIlib_util_send_send_audio_interleaved_32s = Some(util_send_interleaved_32s);
+        table.NDIlib_util_send_send_audio_interleaved_32f = Some(util_send_interleaved_32f);
+    }
+    table
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn NDIlib_v3_load() -> *const sdk::NDIlib_v3 {
+    static TABLE: OnceLock<sdk::NDIlib_v3> = OnceLock::new();
+    TABLE.get_or_init(table)
+}
+
+/// Include the interleaved audio utilities in the table, which must happen before NDIlib_v3_load is first called
+#[no_mangle]
+pub extern "C" fn ndi_stub_enable_util() {
+    UTIL_ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// How many times the interleaved audio utilities have been called
+#[no_mangle]
+pub extern "C" fn ndi_stub_util_calls() -> isize {
+    UTIL_CALLS.load(Ordering::SeqCst)
+}
+
+/// How many times NDIlib_initialize has been called, without a matching NDIlib_destroy
+#[no_mangle]
+pub extern "C" fn ndi_stub_initialized() -> isize {
+    INITIALIZED.load(Ordering::SeqCst)
+}
+
+/// How many captured frames have not been freed
+#[no_mangle]
+pub extern "C" fn ndi_stub_frames_outstanding() -> isize {
+    OUTSTANDING.load(Ordering::SeqCst)
+}
+
+/// How many receivers have been destroyed while frames captured from them were still outstanding
+#[no_mangle]
+pub extern "C" fn ndi_stub_destroyed_early() -> isize {
+    DESTROYED_EARLY.load(Ordering::SeqCst)
+}
+
+/// Queue metadata to be captured by receivers connected to the sender called `name`
+#[no_mangle]
+pub unsafe extern "C" fn ndi_stub_queue_metadata(name: *const c_char, data: *const c_char, timecode: i64) {
+    let name = CStr::from_ptr(name).to_string_lossy();
+    let data = CStr::from_ptr(data).to_owned();
+    with_queue(&name, |q| q.metadata.push_back((data, timecode)));
+}
+
+/// Queue a status change to be reported to receivers connected to the sender called `name`
+#[no_mangle]
+pub unsafe extern "C" fn ndi_stub_queue_status_change(name: *const c_char) {
+    let name = CStr::from_ptr(name).to_string_lossy();
+    with_queue(&name, |q| q.status_changes += 1);
+}
+
+/// Copy the stats for the sender called `name` into `stats`, returning false if there is no such sender
+#[no_mangle]
+pub unsafe extern "C" fn ndi_stub_sender_stats(name: *const c_char, stats: *mut SenderStats) -> bool {
+    let name = CStr::from_ptr(name).to_string_lossy();
+    let senders = SENDERS.lock().unwrap();
+    match senders.as_ref().and_then(|s| s.get(name.as_ref())) {
+        None => false,
+        Some(s) => {
+            *stats = *s;
+            true
+        }
+    }
+}
@@ -0,0 +1,309 @@
+#![cfg(feature = "dynamic-link")]
+
+mod common;
+
+use ndi_sdk::finder::FindInstance;
+use ndi_sdk::receive::{AudioFrame, ReceiveCaptureResult, ReceiveInstance, ReceiveInstanceExt, VideoFrame};
+use ndi_sdk::send::{
+    create_ndi_send_video_frame, FourCCType, FrameFormatType, NDISendAudioFrame, NDISendVideoFrame, SendInstance,
+};
+use ndi_sdk::time::FrameRate;
+use ndi_sdk::NDIInstance;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn assert_send<T: Send>() {}
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn types_can_cross_threads() {
+    assert_send_sync::<NDIInstance>();
+    assert_send_sync::<ReceiveInstance>();
+    assert_send_sync::<SendInstance>();
+    assert_send_sync::<VideoFrame>();
+    assert_send_sync::<AudioFrame>();
+    assert_send::<FindInstance>();
+    assert_send::<NDISendVideoFrame>();
+    assert_send::<NDISendAudioFrame>();
+}
+
+fn video_frame(value: u8) -> NDISendVideoFrame {
+    create_ndi_send_video_frame(4, 2, FrameFormatType::Progressive)
+        .with_framerate(FrameRate::FPS_30)
+        .with_data(vec![value; 4 * 2 * 4], 4 * 4, FourCCType::BGRA)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn video_and_audio_are_sent_from_many_threads() {
+    let instance = common::load();
+    let sender = Arc::new(
+        instance
+            .create_send_instance("threaded_send".to_string(), false, false)
+            .expect("create sender"),
+    );
+
+    let video: Vec<_> = (0..4)
+        .map(|t| {
+            let sender = Arc::clone(&sender);
+            thread::spawn(move || {
+                for i in 0..100 {
+                    if (t + i) % 2 == 0 {
+                        sender.send_video(video_frame(i as u8));
+                    } else {
+                        sender.send_video_async(video_frame(i as u8));
+                    }
+                }
+            })
+        })
+        .collect();
+    let audio: Vec<_> = (0..2)
+        .map(|_| {
+            let sender = Arc::clone(&sender);
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    sender.send_audio_interleaved_f32(&[0.5; 32], 2, 48000).unwrap();
+                }
+            })
+        })
+        .collect();
+    for t in video.into_iter().chain(audio) {
+        t.join().unwrap();
+    }
+    sender.send_video_flush();
+
+    let stats = common::sender_stats("threaded_send");
+    assert_eq!(stats.video_overlaps, 0);
+    assert_eq!(stats.video_frames, 400);
+    assert!(!stats.video_in_flight);
+    assert_eq!(stats.audio_frames, 200);
+}
+
+/// Capture `expected` frames of video or audio, giving up after a few seconds
+fn capture_all(receiver: Arc<ReceiveInstance>, video: bool, expected: usize) -> thread::JoinHandle<usize> {
+    thread::spawn(move || {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut count = 0;
+        while count < expected && Instant::now() < deadline {
+            match receiver.receive_capture(video, !video, false, 0).unwrap() {
+                ReceiveCaptureResult::Video(frame) => {
+                    assert!(video);
+                    assert_eq!(frame.lock_data().len(), 4 * 2 * 4);
+                    count += 1;
+                }
+                ReceiveCaptureResult::Audio(frame) => {
+                    assert!(!video);
+                    assert_eq!(frame.lock_data().channel(1), [0.25; 8]);
+                    count += 1;
+                }
+                _ => thread::yield_now(),
+            }
+        }
+        count
+    })
+}
+
+#[test]
+fn video_and_audio_are_captured_on_separate_threads() {
+    let instance = common::load();
+    let sender = Arc::new(
+        instance
+            .create_send_instance("threaded_receive".to_string(), false, false)
+            .expect("create sender"),
+    );
+    let receiver = common::loopback_receiver(&instance, "threaded_receive");
+
+    let video = capture_all(Arc::clone(&receiver), true, 50);
+    let audio = capture_all(Arc::clone(&receiver), false, 50);
+    drop(receiver);
+
+    let sending: Vec<_> = (0..2)
+        .map(|t| {
+            let sender = Arc::clone(&sender);
+            thread::spawn(move || {
+                for i in 0..50 {
+                    if t == 0 {
+                        sender.send_video(video_frame(i));
+                    } else {
+                        sender.send_audio_interleaved_f32(&[0.25; 16], 2, 48000).unwrap();
+                    }
+                }
+            })
+        })
+        .collect();
+    for t in sending {
+        t.join().unwrap();
+    }
+
+    assert_eq!(video.join().unwrap(), 50);
+    assert_eq!(audio.join().unwrap(), 50);
+    assert_eq!(common::receivers_destroyed_early(), 0);
+}
+
+// A loom model of how `NDIInstance`s share the runtime, in src/instance.rs. Run it with
+// `RUSTFLAGS="--cfg loom" cargo test --release --features dynamic-link --test threads runtime_model`.
+//
+// The runtime is kept behind a mutex, along with a weak reference to the handle responsible for destroying it. loom has
+// no `Weak`, so the strong count of a handle is modelled by hand: upgrading only succeeds while the count is not zero,
+// and the last reference to be dropped releases the runtime, as `Arc` does when it drops the handle
+#[cfg(loom)]
+mod runtime_model {
+    use loom::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use loom::sync::{Arc, Mutex};
+    use loom::thread;
+
+    struct HandleInner {
+        strong: AtomicUsize,
+    }
+
+    struct Runtime {
+        handle: Arc<HandleInner>,
+    }
+
+    struct Sdk {
+        runtime: Mutex<Option<Runtime>>,
+        initialized: AtomicBool,
+    }
+    impl Sdk {
+        fn new() -> Arc<Sdk> {
+            Arc::new(Sdk {
+                runtime: Mutex::new(None),
+                initialized: AtomicBool::new(false),
+            })
+        }
+
+        fn is_unloaded(&self) -> bool {
+            !self.initialized.load(Ordering::SeqCst) && self.runtime.lock().unwrap().is_none()
+        }
+    }
+
+    /// A strong reference to a handle, standing in for `Arc<NDIHandle>`
+    struct Handle {
+        sdk: Arc<Sdk>,
+        inner: Arc<HandleInner>,
+    }
+    impl Handle {
+        fn new(sdk: &Arc<Sdk>, inner: &Arc<HandleInner>) -> Handle {
+            Handle {
+                sdk: Arc::clone(sdk),
+                inner: Arc::clone(inner),
+            }
+        }
+
+        fn call_sdk(&self) {
+            assert!(
+                self.sdk.initialized.load(Ordering::SeqCst),
+                "SDK called after it was destroyed"
+            );
+        }
+    }
+    impl Clone for Handle {
+        fn clone(&self) -> Handle {
+            self.inner.strong.fetch_add(1, Ordering::Relaxed);
+            Handle::new(&self.sdk, &self.inner)
+        }
+    }
+    impl Drop for Handle {
+        fn drop(&mut self) {
+            if self.inner.strong.fetch_sub(1, Ordering::AcqRel) != 1 {
+                return;
+            }
+
+            // `NDIHandle::drop`
+            let mut runtime = self.sdk.runtime.lock().unwrap();
+            let current = runtime.as_ref().is_some_and(|r| Arc::ptr_eq(&r.handle, &self.inner));
+            if current {
+                assert!(
+                    self.sdk.initialized.swap(false, Ordering::SeqCst),
+                    "SDK destroyed twice"
+                );
+                *runtime = None;
+            }
+        }
+    }
+
+    /// `Weak::upgrade`
+    fn upgrade(inner: &HandleInner) -> bool {
+        let mut strong = inner.strong.load(Ordering::Relaxed);
+        while strong != 0 {
+            match inner
+                .strong
+                .compare_exchange(strong, strong + 1, Ordering::Acquire, Ordering::Relaxed)
+            {
+                Ok(_) => return true,
+                Err(actual) => strong = actual,
+            }
+        }
+        false
+    }
+
+    /// `acquire`
+    fn acquire(sdk: &Arc<Sdk>) -> Handle {
+        let mut runtime = sdk.runtime.lock().unwrap();
+
+        if let Some(runtime) = runtime.as_mut() {
+            if upgrade(&runtime.handle) {
+                return Handle::new(sdk, &runtime.handle);
+            }
+
+            let inner = Arc::new(HandleInner {
+                strong: AtomicUsize::new(1),
+            });
+            runtime.handle = Arc::clone(&inner);
+            return Handle::new(sdk, &inner);
+        }
+
+        assert!(!sdk.initialized.swap(true, Ordering::SeqCst), "SDK initialised twice");
+        let inner = Arc::new(HandleInner {
+            strong: AtomicUsize::new(1),
+        });
+        *runtime = Some(Runtime {
+            handle: Arc::clone(&inner),
+        });
+        Handle::new(sdk, &inner)
+    }
+
+    #[test]
+    fn first_loads_race() {
+        loom::model(|| {
+            let sdk = Sdk::new();
+            let threads: Vec<_> = (0..2)
+                .map(|_| {
+                    let sdk = Arc::clone(&sdk);
+                    thread::spawn(move || acquire(&sdk).call_sdk())
+                })
+                .collect();
+            for t in threads {
+                t.join().unwrap();
+            }
+            assert!(sdk.is_unloaded());
+        });
+    }
+
+    #[test]
+    fn load_races_the_last_drop() {
+        loom::model(|| {
+            let sdk = Sdk::new();
+            let handle = acquire(&sdk);
+            let clone = handle.clone();
+
+            let dropping = thread::spawn(move || drop(handle));
+            let loading = {
+                let sdk = Arc::clone(&sdk);
+                thread::spawn(move || {
+                    let handle = acquire(&sdk);
+                    handle.call_sdk();
+                    handle
+                })
+            };
+            drop(clone);
+            dropping.join().unwrap();
+            let loaded = loading.join().unwrap();
+            loaded.call_sdk();
+            drop(loaded);
+            assert!(sdk.is_unloaded());
+        });
+    }
+}
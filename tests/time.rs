@@ -0,0 +1,252 @@
+use ndi_sdk::time::{FrameRate, SmpteTimecode, Timecode, TimecodeError, Timestamp};
+use std::time::{Duration, UNIX_EPOCH};
+
+fn smpte(s: &str) -> SmpteTimecode {
+    s.parse().unwrap()
+}
+
+#[test]
+fn sentinels_are_variants() {
+    assert_eq!(Timecode::from(i64::MAX), Timecode::Synthesize);
+    assert_eq!(i64::from(Timecode::Synthesize), i64::MAX);
+    assert_eq!(Timecode::from(42), Timecode::Value(42));
+    assert_eq!(Timestamp::from(i64::MAX), Timestamp::Undefined);
+    assert_eq!(i64::from(Timestamp::Value(-1)), -1);
+
+    assert_eq!(Timecode::Synthesize.offset(10), Timecode::Synthesize);
+    assert_eq!(Timestamp::Value(5).offset(10), Timestamp::Value(15));
+    assert!(Timestamp::default().is_undefined());
+    assert_eq!(Timecode::Synthesize.to_smpte(FrameRate::FPS_25, false), None);
+}
+
+#[test]
+fn offsets_saturate_before_the_sentinels() {
+    assert_eq!(Timecode::Value(i64::MAX - 5).offset(10), Timecode::Value(i64::MAX - 1));
+    assert_eq!(Timecode::Value(i64::MIN + 5).offset(-10), Timecode::Value(i64::MIN));
+    assert_eq!(
+        Timestamp::Value(i64::MAX - 5).offset(i64::MAX),
+        Timestamp::Value(i64::MAX - 1)
+    );
+    assert_eq!(Timestamp::Value(-5).offset(i64::MIN), Timestamp::Value(i64::MIN));
+}
+
+#[test]
+fn timestamps_are_system_times() {
+    let time = UNIX_EPOCH + Duration::new(1_600_000_000, 123_456_700);
+    let timestamp = Timestamp::from(time);
+    assert_eq!(timestamp, Timestamp::Value(16_000_000_001_234_567));
+    assert_eq!(timestamp.to_system_time(), Some(time));
+    assert_eq!(timestamp.to_duration(), Some(Duration::new(1_600_000_000, 123_456_700)));
+
+    let before = UNIX_EPOCH - Duration::from_secs(1);
+    assert_eq!(Timestamp::from(before), Timestamp::Value(-10_000_000));
+    assert_eq!(Timestamp::from(before).to_system_time(), Some(before));
+    assert_eq!(Timestamp::from(before).to_duration(), None);
+    assert_eq!(Timestamp::Undefined.to_system_time(), None);
+}
+
+#[test]
+fn smpte_strings() {
+    let label = smpte("01:02:03;04");
+    assert_eq!(
+        label,
+        SmpteTimecode {
+            hours: 1,
+            minutes: 2,
+            seconds: 3,
+            frames: 4,
+            drop_frame: true
+        }
+    );
+    assert_eq!(label.to_string(), "01:02:03;04");
+    assert!(!smpte("10:00:00:00").drop_frame);
+    assert!(smpte("10:00:00.00").drop_frame);
+    assert_eq!(smpte("01;02;03;04"), label);
+    assert_eq!(smpte("01.02.03.04"), label);
+
+    let mixed = ["01:02;03:04", "01;02;03:04", "01;02:03;04", "01.02;03;04"];
+    for s in ["1:2:3", "01:02:03:", "01:02:0x:04", "01-02-03-04"]
+        .iter()
+        .chain(&mixed)
+    {
+        assert_eq!(
+            s.parse::<SmpteTimecode>(),
+            Err(TimecodeError::InvalidFormat(s.to_string()))
+        );
+    }
+}
+
+#[test]
+fn whole_frame_rates() {
+    // One hour, one minute, one second and twelve frames at 25 fps
+    let timecode = Timecode::Value(3661 * 10_000_000 + 12 * 400_000);
+    assert_eq!(timecode.to_smpte(FrameRate::FPS_25, false), Some(smpte("01:01:01:12")));
+    assert_eq!(
+        Timecode::from_smpte(&smpte("01:01:01:12"), FrameRate::FPS_25),
+        Ok(timecode)
+    );
+
+    // Anywhere within a frame gives its label
+    assert_eq!(
+        Timecode::Value(399_999).to_smpte(FrameRate::FPS_25, false),
+        Some(smpte("00:00:00:00"))
+    );
+    // And the hours wrap around each day
+    assert_eq!(
+        Timecode::Value(86_400 * 10_000_000).to_smpte(FrameRate::FPS_50, false),
+        Some(smpte("00:00:00:00"))
+    );
+
+    assert_eq!(
+        Timecode::from_smpte(&smpte("00:00:00:25"), FrameRate::FPS_25),
+        Err(TimecodeError::OutOfRange(smpte("00:00:00:25")))
+    );
+    assert_eq!(
+        Timecode::from_smpte(&smpte("00:00:00;00"), FrameRate::FPS_25),
+        Err(TimecodeError::DropFrameUnsupported(FrameRate::FPS_25))
+    );
+    assert_eq!(Timecode::Value(0).to_smpte(FrameRate::FPS_25, true), None);
+    assert_eq!(
+        Timecode::from_smpte(&smpte("00:00:00:00"), FrameRate::new(0, 1)),
+        Err(TimecodeError::InvalidFrameRate(FrameRate::new(0, 1)))
+    );
+}
+
+#[test]
+fn drop_frame_skips_labels() {
+    let frame = |n: i64| Timecode::Value((n * 1001 * 10_000_000 + 29_999) / 30_000);
+
+    assert_eq!(
+        frame(1799).to_smpte(FrameRate::FPS_29_97, true),
+        Some(smpte("00:00:59;29"))
+    );
+    assert_eq!(
+        frame(1800).to_smpte(FrameRate::FPS_29_97, true),
+        Some(smpte("00:01:00;02"))
+    );
+    // Every tenth minute keeps its first two labels
+    assert_eq!(
+        frame(17982).to_smpte(FrameRate::FPS_29_97, true),
+        Some(smpte("00:10:00;00"))
+    );
+    // So that an hour of labels is very nearly an hour
+    assert_eq!(
+        frame(107_892).to_smpte(FrameRate::FPS_29_97, true),
+        Some(smpte("01:00:00;00"))
+    );
+
+    assert_eq!(
+        Timecode::from_smpte(&smpte("00:01:00;01"), FrameRate::FPS_29_97),
+        Err(TimecodeError::OutOfRange(smpte("00:01:00;01")))
+    );
+    // The label of a later frame without drop frame
+    assert_eq!(
+        Timecode::from_smpte(&smpte("00:01:00:02"), FrameRate::FPS_29_97),
+        Ok(frame(1802))
+    );
+
+    // 59.94 fps skips four labels instead
+    assert_eq!(
+        Timecode::Value(60 * 10_000_000).to_smpte(FrameRate::FPS_59_94, true),
+        Some(smpte("00:00:59;56"))
+    );
+    assert_eq!(
+        Timecode::from_smpte(&smpte("00:01:00;04"), FrameRate::FPS_59_94),
+        Ok(Timecode::Value((3600 * 1001 * 10_000_000 + 59_999) / 60_000))
+    );
+}
+
+#[test]
+fn non_drop_frame_at_ntsc_rates() {
+    let frame = |n: i64| Timecode::Value((n * 1001 * 10_000_000 + 29_999) / 30_000);
+
+    // Every label is used, so the labels fall behind the wall clock
+    assert_eq!(
+        frame(1800).to_smpte(FrameRate::FPS_29_97, false),
+        Some(smpte("00:01:00:00"))
+    );
+    assert_eq!(
+        frame(107_892).to_smpte(FrameRate::FPS_29_97, false),
+        Some(smpte("00:59:56:12"))
+    );
+    assert_eq!(
+        Timecode::from_smpte(&smpte("00:01:00:00"), FrameRate::FPS_29_97),
+        Ok(frame(1800))
+    );
+    assert_eq!(
+        Timecode::from_smpte(&smpte("00:01:00:00"), FrameRate::FPS_23_976),
+        Ok(Timecode::Value((1440 * 1001 * 10_000_000 + 23_999) / 24_000))
+    );
+
+    // 23.976 fps has no drop frame form
+    assert_eq!(Timecode::Value(0).to_smpte(FrameRate::FPS_23_976, true), None);
+    assert_eq!(
+        Timecode::from_smpte(&smpte("00:01:00;00"), FrameRate::FPS_23_976),
+        Err(TimecodeError::DropFrameUnsupported(FrameRate::FPS_23_976))
+    );
+
+    assert!(FrameRate::FPS_29_97.supports_drop_frame());
+    assert!(FrameRate::FPS_59_94.supports_drop_frame());
+    assert!(FrameRate::FPS_119_88.supports_drop_frame());
+    assert!(!FrameRate::FPS_23_976.supports_drop_frame());
+    assert!(!FrameRate::FPS_30.supports_drop_frame());
+}
+
+#[test]
+fn frame_rates() {
+    assert_eq!(FrameRate::FPS_29_97, FrameRate::new(30000, 1001));
+    assert_eq!(FrameRate::FPS_29_97.to_string(), "30000/1001");
+    assert!((f64::from(FrameRate::FPS_59_94) - 59.94).abs() < 0.001);
+    assert_eq!(FrameRate::FPS_25.frame_duration(), Some(Duration::from_millis(40)));
+    assert_eq!(
+        FrameRate::FPS_23_976.frame_duration(),
+        Some(Duration::from_nanos(41_708_333))
+    );
+    assert_eq!(FrameRate::new(0, 0).frame_duration(), None);
+    assert!(!FrameRate::new(30, 0).is_valid());
+
+    assert_eq!(FrameRate::from_f64(25.0), Some(FrameRate::FPS_25));
+    assert_eq!(FrameRate::from_f64(29.97), Some(FrameRate::FPS_29_97));
+    assert_eq!(FrameRate::from_f64(23.976), Some(FrameRate::FPS_23_976));
+    assert_eq!(FrameRate::from_f64(59.94), Some(FrameRate::FPS_59_94));
+    assert_eq!(FrameRate::from_f64(23.98), Some(FrameRate::FPS_23_976));
+    assert_eq!(FrameRate::from_f64(12.5), Some(FrameRate::new(12500, 1000)));
+    // Rates which are only close to an NTSC rate in absolute terms, or not near a broadcast rate, are kept
+    assert_eq!(FrameRate::from_f64(2.005), Some(FrameRate::new(2005, 1000)));
+    assert_eq!(FrameRate::from_f64(1.0005), Some(FrameRate::new(1001, 1000)));
+    assert_eq!(FrameRate::from_f64(5.994), Some(FrameRate::new(5994, 1000)));
+    assert_eq!(FrameRate::from_f64(0.02), Some(FrameRate::new(20, 1000)));
+    assert_eq!(FrameRate::from_f64(0.0), None);
+    // Rates too small to be represented are not rounded down to zero
+    assert_eq!(FrameRate::from_f64(0.005), Some(FrameRate::new(5, 1000)));
+    assert_eq!(FrameRate::from_f64(1e-7), None);
+    assert_eq!(FrameRate::from_f64(0.0004), None);
+    assert_eq!(FrameRate::from_f64(f64::NAN), None);
+}
+
+#[test]
+fn labels_round_trip() {
+    for &(rate, drop_frame) in [
+        (FrameRate::FPS_29_97, true),
+        (FrameRate::FPS_29_97, false),
+        (FrameRate::FPS_59_94, true),
+        (FrameRate::FPS_23_976, false),
+        (FrameRate::FPS_25, false),
+    ]
+    .iter()
+    {
+        let mut last = None;
+        for i in (0..200_000).step_by(7) {
+            let timecode = Timecode::Value(i * 10_000_000 / 1000);
+            let label = timecode.to_smpte(rate, drop_frame).unwrap();
+            assert_eq!(label.drop_frame, drop_frame);
+            let start = Timecode::from_smpte(&label, rate).unwrap();
+            assert_eq!(start.to_smpte(rate, drop_frame), Some(label));
+            assert!(start.value() <= timecode.value(), "{} at {}", label, rate);
+            if let Some(last) = last {
+                assert!(label.to_string() >= last, "{} at {}", label, rate);
+            }
+            last = Some(label.to_string());
+        }
+    }
+}